@@ -8,6 +8,8 @@ program.version('0.1.0')
   .option('-c, --concurrency <number>', 'number of files that can be processed at once')
   .option('-e, --extension <extension>', 'output file extension')
   .option('-w, --write', 'write output to file')
+  .option('-r, --report', 'print a summary of the migration to stderr')
+  .option('--resolve', 'add file extensions to relative imports, using --extension for converted files')
   .arguments('<file>', 'Global patterns of files to transform. Wrap in quotes to avoid shell expansion.')
   .parse(process.argv);
 
@@ -19,4 +21,6 @@ transform({
   concurrency: options.concurrency,
   outputExtension: options.extension,
   write: options.write,
+  report: options.report,
+  resolve: options.resolve,
 });
\ No newline at end of file
@@ -3,13 +3,27 @@ import {globbySync} from 'globby';
 import fs from 'node:fs';
 import path from 'node:path';
 
+// The plugin reports what it did in a comment at the top of each file, ie `// cjs-to-esm-report {...}`
+const REPORT_COMMENT = /^\/\/ cjs-to-esm-report (.*)\n/;
+
+// Adds up the numbers of two reports, field by field
+function addReports(total, report) {
+  for (const [key, value] of Object.entries(report)) {
+    total[key] = typeof value === 'object' ? addReports(total[key] ?? {}, value) : (total[key] ?? 0) + value;
+  }
+  return total;
+}
+
 export async function transform({
   globs,
   ignore,
   write = false,
   outputExtension, 
   concurrency = 15, // number of files that can be processed at once 
+  report = false, // print a summary of the migration to stderr
+  resolve = false, // add file extensions to relative imports
 } = {}) {
+  const summary = {files: 0, filesWithLeftovers: [], totals: {}};
   const files = globbySync(globs, {onlyFiles: true, ignore: ignore?.split?.(',') ?? []});
   // Maybe split the work into multiple worker threads at some point? swc is pretty fast though so maybe 
   // we should just limit the number of files processed at once.
@@ -19,19 +33,32 @@ export async function transform({
       const filename = path.basename(file);
       const newFilename = outputExtension ? filename.replace(path.extname(filename), outputExtension) : filename;
       const outputFile = path.join(path.dirname(file), newFilename);
-      const {code} = swc.transformFileSync(file, {
+      let {code} = swc.transformFileSync(file, {
         sourceMaps: false,
         jsc: {
           preserveAllComments: true,
           target: 'es2019',
           experimental: {
             plugins: [
-              ['swc-plugin-cjs-to-esm', {}]
+              ['swc-plugin-cjs-to-esm', {report, resolve, extension: outputExtension}]
             ]
           }
         },
         swcrc: false,
       });
+      if(report) {
+        const [comment, json] = code.match(REPORT_COMMENT) ?? [];
+        if(comment) {
+          const {warnings = [], ...fileReport} = JSON.parse(json);
+          code = code.slice(comment.length);
+          warnings.forEach(({message}) => console.error(`${file}: warning: ${message}`));
+          summary.files++;
+          addReports(summary.totals, fileReport);
+          if(fileReport.remainingRequires || fileReport.remainingModuleRefs) {
+            summary.filesWithLeftovers.push(file);
+          }
+        }
+      }
       if(write) {
         fs.writeFileSync(outputFile, code);
       } else {
@@ -39,4 +66,7 @@ export async function transform({
       }
     }));
   }
-}
\ No newline at end of file
+  if(report) {
+    console.error(JSON.stringify(summary, null, 2));
+  }
+}
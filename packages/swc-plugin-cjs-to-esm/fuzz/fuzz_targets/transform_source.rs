@@ -0,0 +1,27 @@
+//! Feeds arbitrary source to `transform_source()`. It must not panic, and whenever the input parses,
+//! the output has to parse again as well.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use swc_plugin_cjs_to_esm::{transform_source, Options, Severity};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(src) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(output) = transform_source(src, "input.js", &Options::default()) else {
+        return;
+    };
+    // Recoverable parse errors, the input wasn't valid to begin with
+    if output.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        return;
+    }
+    let reparsed = transform_source(&output.code, "output.js", &Options::default())
+        .unwrap_or_else(|err| panic!("output does not parse: {}\n{}", err, output.code));
+    assert!(
+        !reparsed.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error),
+        "output does not parse:\n{}",
+        output.code
+    );
+});
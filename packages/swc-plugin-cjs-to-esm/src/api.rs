@@ -0,0 +1,298 @@
+use std::{fmt, rc::Rc};
+
+use swc_core::{
+    common::{
+        chain, comments::SingleThreadedComments, sync::Lrc, BytePos, FileName, LineCol, Mark, SourceMap, Span, Spanned,
+        GLOBALS,
+    },
+    ecma::{
+        codegen::{text_writer::{JsWriter, WriteJs}, Emitter},
+        parser::{error::Error as ParseError, EsConfig, Parser, StringInput, Syntax, TsConfig},
+        transforms::base::resolver,
+        visit::FoldWith,
+    },
+};
+
+use crate::{
+    config::CjsToEsmConfig,
+    project::{ExportTable, ProjectFile},
+    report::{ConversionReport, SharedReport},
+    transform_cjs::{cjs_to_esm, emit_report_comment, resolve_relative_specifiers},
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// Also build a source map for the output
+    pub source_map: bool,
+    /// The same options that can be passed to the swc plugin
+    pub config: CjsToEsmConfig,
+    /// Exports of the other files of the project, see `ExportTable::scan()`
+    pub project: Option<Rc<ExportTable>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// The source as it was when the file was skipped, see `ConversionReport::skipped`
+    pub code: String,
+    /// Source map as json, when asked for with `Options::source_map`
+    pub map: Option<String>,
+    pub report: ConversionReport,
+    /// Recoverable parse errors and the warnings from the report, with their location in the source
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// 1-based, 0 when the location is unknown
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}:{}: {}: {}", self.line, self.column, severity, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The source could not be parsed as a module
+    Parse(Diagnostic),
+    Emit(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(diagnostic) => write!(f, "failed to parse: {}", diagnostic),
+            Error::Emit(err) => write!(f, "failed to emit code: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/**
+    Picks the syntax to parse a file with from its extension, TypeScript for `.ts`, `.mts`, `.cts` and `.tsx`
+    and JSX for `.jsx` and `.tsx`.
+ */
+pub fn syntax_for_path(filename: &str) -> Syntax {
+    match filename.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("jsx") => Syntax::Es(EsConfig { jsx: true, ..Default::default() }),
+        Some("ts" | "mts" | "cts") => Syntax::Typescript(Default::default()),
+        Some("tsx") => Syntax::Typescript(TsConfig { tsx: true, ..Default::default() }),
+        _ => Syntax::default(),
+    }
+}
+
+/**
+    Parses `src` as a module, runs `cjs_to_esm()` over it and prints the result.
+    `filename` picks the syntax, see `syntax_for_path()`, and is used for the source map and to find the file in
+    `Options::project`.
+
+    ```
+    use swc_plugin_cjs_to_esm::{transform_source, Options};
+
+    let output = transform_source("const foo = require('foo');", "index.js", &Options::default()).unwrap();
+    assert_eq!(output.code, "import * as foo from 'foo';\n");
+    ```
+ */
+pub fn transform_source(src: &str, filename: &str, options: &Options) -> Result<Output> {
+    GLOBALS.set(&Default::default(), || {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Real(filename.into()), src.into());
+        let comments = Rc::new(SingleThreadedComments::default());
+        let report = SharedReport::default();
+
+        let syntax = syntax_for_path(filename);
+        let mut parser = Parser::new(syntax, StringInput::from(&*fm), Some(&comments));
+        let module = parser
+            .parse_module()
+            .map_err(|err| Error::Parse(parse_diagnostic(&cm, &err)))?;
+        let mut diagnostics: Vec<Diagnostic> = parser
+            .take_errors()
+            .iter()
+            .map(|err| parse_diagnostic(&cm, err))
+            .collect();
+
+        let mut module = module.fold_with(&mut chain!(
+            resolver(Mark::new(), Mark::new(), syntax.typescript()),
+            cjs_to_esm(
+                Some(comments.clone()),
+                report.clone(),
+                options.project.clone().map(|exports| ProjectFile::new(exports, filename)),
+                &options.config,
+            ),
+        ));
+        let mut mappings: Vec<(BytePos, LineCol)> = vec![];
+        let code = if report.borrow().skipped {
+            // Skipped files are handed back byte for byte, printing them again would reformat them
+            if options.source_map {
+                mappings.extend(fm.lines.iter().enumerate().map(|(line, pos)| (*pos, LineCol { line: line as u32, col: 0 })));
+            }
+            src.to_owned()
+        } else {
+            if options.config.resolve() {
+                module = module.fold_with(&mut resolve_relative_specifiers(
+                    filename.into(),
+                    options.config.extension.clone(),
+                    options.config.json_attributes(),
+                    report.clone(),
+                ));
+            }
+            if options.config.report {
+                module = module.fold_with(&mut emit_report_comment(Some(comments.clone()), report.clone()));
+            }
+
+            let mut code = vec![];
+            Emitter {
+                cfg: Default::default(),
+                cm: cm.clone(),
+                comments: Some(&comments),
+                wr: ImportAttributesWriter(JsWriter::new(
+                    cm.clone(),
+                    "\n",
+                    &mut code,
+                    options.source_map.then_some(&mut mappings),
+                )),
+            }
+            .emit_module(&module)
+            .map_err(Error::Emit)?;
+            String::from_utf8(code).expect("codegen emits utf-8")
+        };
+
+        let map = if options.source_map {
+            let mut map = vec![];
+            cm.build_source_map(&mappings)
+                .to_writer(&mut map)
+                .map_err(|err| Error::Emit(std::io::Error::other(err)))?;
+            Some(String::from_utf8(map).expect("source maps are json"))
+        } else {
+            None
+        };
+
+        let report = report.borrow().clone();
+        diagnostics.extend(
+            report
+                .warnings
+                .iter()
+                .map(|warning| diagnostic(&cm, Severity::Warning, warning.span, warning.message.clone())),
+        );
+
+        Ok(Output {
+            code,
+            map,
+            report,
+            diagnostics,
+        })
+    })
+}
+
+/**
+    Prints import attributes with `with`, which node 22 and browsers need. The codegen of this swc version only knows
+    the older `assert`, which is the only thing it writes that keyword for.
+ */
+struct ImportAttributesWriter<W: WriteJs>(W);
+
+impl<W: WriteJs> WriteJs for ImportAttributesWriter<W> {
+    fn increase_indent(&mut self) -> std::io::Result<()> {
+        self.0.increase_indent()
+    }
+
+    fn decrease_indent(&mut self) -> std::io::Result<()> {
+        self.0.decrease_indent()
+    }
+
+    fn write_semi(&mut self, span: Option<Span>) -> std::io::Result<()> {
+        self.0.write_semi(span)
+    }
+
+    fn write_space(&mut self) -> std::io::Result<()> {
+        self.0.write_space()
+    }
+
+    fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> std::io::Result<()> {
+        self.0.write_keyword(span, if s == "assert" { "with" } else { s })
+    }
+
+    fn write_operator(&mut self, span: Option<Span>, s: &str) -> std::io::Result<()> {
+        self.0.write_operator(span, s)
+    }
+
+    fn write_param(&mut self, s: &str) -> std::io::Result<()> {
+        self.0.write_param(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> std::io::Result<()> {
+        self.0.write_property(s)
+    }
+
+    fn write_line(&mut self) -> std::io::Result<()> {
+        self.0.write_line()
+    }
+
+    fn write_lit(&mut self, span: Span, s: &str) -> std::io::Result<()> {
+        self.0.write_lit(span, s)
+    }
+
+    fn write_comment(&mut self, s: &str) -> std::io::Result<()> {
+        self.0.write_comment(s)
+    }
+
+    fn write_str_lit(&mut self, span: Span, s: &str) -> std::io::Result<()> {
+        self.0.write_str_lit(span, s)
+    }
+
+    fn write_str(&mut self, s: &str) -> std::io::Result<()> {
+        self.0.write_str(s)
+    }
+
+    fn write_symbol(&mut self, span: Span, s: &str) -> std::io::Result<()> {
+        self.0.write_symbol(span, s)
+    }
+
+    fn write_punct(&mut self, span: Option<Span>, s: &'static str) -> std::io::Result<()> {
+        self.0.write_punct(span, s)
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        self.0.care_about_srcmap()
+    }
+
+    fn add_srcmap(&mut self, pos: BytePos) -> std::io::Result<()> {
+        self.0.add_srcmap(pos)
+    }
+
+    fn commit_pending_semi(&mut self) -> std::io::Result<()> {
+        self.0.commit_pending_semi()
+    }
+}
+
+fn parse_diagnostic(cm: &SourceMap, err: &ParseError) -> Diagnostic {
+    diagnostic(cm, Severity::Error, err.span(), err.kind().msg().into_owned())
+}
+
+fn diagnostic(cm: &SourceMap, severity: Severity, span: Span, message: String) -> Diagnostic {
+    let (line, column) = if span.is_dummy() {
+        (0, 0)
+    } else {
+        let loc = cm.lookup_char_pos(span.lo);
+        (loc.line, loc.col.0 + 1)
+    };
+    Diagnostic { severity, message, line, column }
+}
@@ -0,0 +1,232 @@
+/*!
+    Command line driver for the transform, the native counterpart to `mdnzr`.
+
+    ```sh
+    cjs-to-esm --write --extension .mjs 'src/**/*.js'
+    ```
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+};
+
+use clap::Parser;
+use glob::{glob, Pattern};
+use serde::de::DeserializeOwned;
+use swc_plugin_cjs_to_esm::{
+    config::{CjsToEsmConfig, Dirname, MainModule, QuotesStyle, RequireResolve, SideEffects, Target}, project::ExportTable, report::ConversionReport, transform_source, Options,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Converts CommonJS files to ES modules")]
+struct Cli {
+    /// Glob patterns of files to transform. Wrap in quotes to avoid shell expansion.
+    #[arg(required = true)]
+    globs: Vec<String>,
+    /// Patterns to ignore, separated by comma
+    #[arg(short, long)]
+    ignore: Option<String>,
+    /// Output file extension, ie `.mjs`
+    #[arg(short, long)]
+    extension: Option<String>,
+    /// Write output next to the input files, replacing them unless `--extension` is given
+    #[arg(short, long, conflicts_with = "out_dir")]
+    write: bool,
+    /// Write output into this directory, keeping the paths of the input files relative to the directory that holds
+    /// them and the working directory
+    #[arg(short, long)]
+    out_dir: Option<PathBuf>,
+    /// Print a summary of the migration to stderr
+    #[arg(short, long)]
+    report: bool,
+    /// Scan the exports of all files first, so requires of them become named or default imports to match
+    #[arg(short, long)]
+    project: bool,
+    /// Quotes of the module specifiers: `preserve`, `single` or `double`
+    #[arg(long, default_value = "preserve", value_parser = parse_option::<QuotesStyle>)]
+    quotes_style: QuotesStyle,
+    /// Where the converted code runs: `node18`, `node22`, `browser`, `vite` or `webpack`. Picks the defaults of the
+    /// options below
+    #[arg(long, value_parser = parse_option::<Target>)]
+    target: Option<Target>,
+    /// Add file extensions to relative imports, using `--extension` for converted files
+    #[arg(long, overrides_with = "no_resolve")]
+    resolve: bool,
+    /// Leave relative imports as they are, even when `--target` resolves them
+    #[arg(long, overrides_with = "resolve")]
+    no_resolve: bool,
+    /// What to do with requires after code with side effects: `warn`, or keep them with `createRequire`
+    #[arg(long, value_parser = parse_option::<SideEffects>)]
+    side_effects: Option<SideEffects>,
+    /// What `require.resolve()` becomes: `importMetaResolve`, `url` to use `new URL()` for relative paths, or `warn`
+    /// to leave it as it is
+    #[arg(long, value_parser = parse_option::<RequireResolve>)]
+    require_resolve: Option<RequireResolve>,
+    /// What `require.main === module` becomes: `processArgv`, or `importMetaMain` for runtimes with `import.meta.main`
+    #[arg(long, value_parser = parse_option::<MainModule>)]
+    main_module: Option<MainModule>,
+    /// Convert requires of relative template literals to eager `import.meta.glob()` lookups, for Vite
+    #[arg(long, overrides_with = "no_glob_imports")]
+    glob_imports: bool,
+    /// Leave requires of template literals as they are, even when `--target` converts them
+    #[arg(long, overrides_with = "glob_imports")]
+    no_glob_imports: bool,
+    /// What `__dirname` and `__filename` become: `url` to declare them with `fileURLToPath(import.meta.url)`,
+    /// `importMeta` for `import.meta.dirname`, or `warn` to leave them as they are
+    #[arg(long, value_parser = parse_option::<Dirname>)]
+    dirname: Option<Dirname>,
+    /// Add `with { type: 'json' }` to imports of JSON files
+    #[arg(long, overrides_with = "no_json_attributes")]
+    json_attributes: bool,
+    /// Leave imports of JSON files without attributes, even when `--target` adds them
+    #[arg(long, overrides_with = "json_attributes")]
+    no_json_attributes: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let files = match collect_files(&cli.globs, cli.ignore.as_deref()) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let base = match cli.out_dir.is_some().then(|| common_base(&files)).transpose() {
+        Ok(base) => base,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = CjsToEsmConfig {
+        quotes_style: cli.quotes_style,
+        target: cli.target,
+        resolve: flag(cli.resolve, cli.no_resolve),
+        side_effects: cli.side_effects,
+        require_resolve: cli.require_resolve,
+        main_module: cli.main_module,
+        glob_imports: flag(cli.glob_imports, cli.no_glob_imports),
+        dirname: cli.dirname,
+        json_attributes: flag(cli.json_attributes, cli.no_json_attributes),
+        extension: cli.extension.clone(),
+        ..Default::default()
+    };
+    // Scanned with the same options, so the exports match what the files are converted to
+    let options = Options {
+        project: cli.project.then(|| Rc::new(ExportTable::scan(&files, &config))),
+        config,
+        ..Default::default()
+    };
+    let mut total = ConversionReport::default();
+    let mut failed = false;
+    for file in files {
+        match transform_file(&file, &options) {
+            Ok((code, report)) => {
+                total.merge(&report);
+                if let Err(err) = write_output(&cli, base.as_deref(), &file, &code) {
+                    eprintln!("{}: {}", file.display(), err);
+                    failed = true;
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", file.display(), err);
+                failed = true;
+            }
+        }
+    }
+
+    if cli.report {
+        eprintln!("{}", serde_json::to_string_pretty(&total).unwrap());
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// `None` when neither `--foo` nor `--no-foo` is given, so the default of the target applies
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+// Same names as in the plugin config
+fn parse_option<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_owned())).map_err(|err| err.to_string())
+}
+
+fn collect_files(globs: &[String], ignore: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let ignore = ignore
+        .unwrap_or_default()
+        .split(',')
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| Pattern::new(pattern).map_err(|err| format!("invalid ignore pattern `{}`: {}", pattern, err)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut files = vec![];
+    for pattern in globs {
+        let paths = glob(pattern).map_err(|err| format!("invalid glob `{}`: {}", pattern, err))?;
+        for path in paths.flatten() {
+            if path.is_file() && !ignore.iter().any(|ignore| ignore.matches_path(&path)) && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn transform_file(file: &Path, options: &Options) -> Result<(String, ConversionReport), String> {
+    let src = fs::read_to_string(file).map_err(|err| err.to_string())?;
+    let output = transform_source(&src, &file.to_string_lossy(), options).map_err(|err| err.to_string())?;
+    for diagnostic in &output.diagnostics {
+        eprintln!("{}:{}", file.display(), diagnostic);
+    }
+    Ok((output.code, output.report))
+}
+
+fn write_output(cli: &Cli, base: Option<&Path>, file: &Path, code: &str) -> std::io::Result<()> {
+    let output = match (&cli.out_dir, base, cli.write) {
+        (Some(out_dir), Some(base), _) => out_dir.join(relative_path(base, file)?),
+        (_, _, true) => file.to_owned(),
+        _ => {
+            println!("{}", code);
+            return Ok(());
+        }
+    };
+    let output = match &cli.extension {
+        Some(extension) => output.with_extension(extension.trim_start_matches('.')),
+        None => output,
+    };
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, code)
+}
+
+// The deepest directory that holds the working directory and all inputs, so every input has its own path under
+// the out dir, `../lib/index.js` included
+fn common_base(files: &[PathBuf]) -> Result<PathBuf, String> {
+    let mut base = std::env::current_dir().and_then(fs::canonicalize).map_err(|err| err.to_string())?;
+    for file in files {
+        let file = fs::canonicalize(file).map_err(|err| format!("{}: {}", file.display(), err))?;
+        while !file.starts_with(&base) {
+            if !base.pop() {
+                return Err(format!("{}: not on the same drive as the working directory", file.display()));
+            }
+        }
+    }
+    Ok(base)
+}
+
+fn relative_path(base: &Path, file: &Path) -> std::io::Result<PathBuf> {
+    Ok(fs::canonicalize(file)?.strip_prefix(base).map(Path::to_owned).unwrap_or_default())
+}
@@ -1,4 +1,184 @@
+use serde::Deserialize;
 
+/**
+    The options after `target` are decided by its preset when they aren't given, or by the defaults of the plugin
+    when there is no target either. Read them with the methods of the same name.
+ */
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct CjsToEsmConfig {
-  pub quotes_style: String,
-}
\ No newline at end of file
+  /// Quotes of the module specifiers in the output
+  pub quotes_style: QuotesStyle,
+  /// Adds a `// cjs-to-esm-report {...}` comment with a summary of the conversion to the top of each file
+  pub report: bool,
+  /// Extension the converted files will have, ie `.mjs`. Used for the specifiers rewritten by `resolve`
+  pub extension: Option<String>,
+  /// Where the converted code runs, picks the defaults of the options below
+  pub target: Option<Target>,
+  /// Rewrites relative specifiers to the files they resolve to, ie `./foo` to `./foo.js`
+  pub resolve: Option<bool>,
+  /// What to do with requires that come after code with side effects, which would run before that code once hoisted
+  pub side_effects: Option<SideEffects>,
+  /// What `require.resolve()` becomes
+  pub require_resolve: Option<RequireResolve>,
+  /// What `require.main === module` becomes
+  pub main_module: Option<MainModule>,
+  /// Converts requires of relative template literals to eager `import.meta.glob()` lookups, for Vite
+  pub glob_imports: Option<bool>,
+  /// What `__dirname` and `__filename` become
+  pub dirname: Option<Dirname>,
+  /// Adds `with { type: 'json' }` to imports of JSON files, which node needs to import them
+  pub json_attributes: Option<bool>,
+}
+
+impl CjsToEsmConfig {
+  fn preset(&self) -> Preset {
+    self.target.map(Target::preset).unwrap_or_default()
+  }
+
+  pub fn resolve(&self) -> bool {
+    self.resolve.unwrap_or_else(|| self.preset().resolve)
+  }
+
+  pub fn side_effects(&self) -> SideEffects {
+    self.side_effects.unwrap_or_else(|| self.preset().side_effects)
+  }
+
+  pub fn require_resolve(&self) -> RequireResolve {
+    self.require_resolve.unwrap_or_else(|| self.preset().require_resolve)
+  }
+
+  pub fn main_module(&self) -> MainModule {
+    self.main_module.unwrap_or_else(|| self.preset().main_module)
+  }
+
+  pub fn glob_imports(&self) -> bool {
+    self.glob_imports.unwrap_or_else(|| self.preset().glob_imports)
+  }
+
+  pub fn dirname(&self) -> Dirname {
+    self.dirname.unwrap_or_else(|| self.preset().dirname)
+  }
+
+  pub fn json_attributes(&self) -> bool {
+    self.json_attributes.unwrap_or_else(|| self.preset().json_attributes)
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotesStyle {
+  /// Keep the quotes of the `require` calls, generated imports get single quotes
+  #[default]
+  Preserve,
+  Single,
+  Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Target {
+  /// Node 18, which has no `import.meta.resolve()` yet
+  Node18,
+  /// Node 22
+  Node22,
+  /// Browsers, which have neither `module` nor `url` nor `process`
+  Browser,
+  /// Code bundled by Vite, for the browser
+  Vite,
+  /// Code bundled by webpack, for the browser
+  Webpack,
+}
+
+impl Target {
+  fn preset(self) -> Preset {
+    match self {
+      // `createRequire()` can't load the files that were converted to ES modules before node 22.12
+      Target::Node18 => Preset {
+        resolve: true,
+        require_resolve: RequireResolve::Url,
+        json_attributes: true,
+        ..Default::default()
+      },
+      // `import.meta.dirname` and `import.meta.filename` came with node 20.11
+      Target::Node22 => Preset {
+        resolve: true,
+        side_effects: SideEffects::CreateRequire,
+        dirname: Dirname::ImportMeta,
+        json_attributes: true,
+        ..Default::default()
+      },
+      Target::Browser | Target::Webpack => Preset {
+        require_resolve: RequireResolve::Warn,
+        main_module: MainModule::ImportMetaMain,
+        dirname: Dirname::Warn,
+        ..Default::default()
+      },
+      Target::Vite => Preset {
+        require_resolve: RequireResolve::Warn,
+        main_module: MainModule::ImportMetaMain,
+        glob_imports: true,
+        dirname: Dirname::Warn,
+        ..Default::default()
+      },
+    }
+  }
+}
+
+// The options a target decides, the default is what the plugin does without one
+#[derive(Default)]
+struct Preset {
+  resolve: bool,
+  side_effects: SideEffects,
+  require_resolve: RequireResolve,
+  main_module: MainModule,
+  glob_imports: bool,
+  dirname: Dirname,
+  json_attributes: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SideEffects {
+  /// Hoist the import anyway and add a warning to the report
+  #[default]
+  Warn,
+  /// Keep the `require`, made with `createRequire(import.meta.url)` from node's `module`. Needs node 22.12 or newer
+  /// once the required file is converted as well, older versions can't `require` ES modules
+  CreateRequire,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RequireResolve {
+  /// `fileURLToPath(import.meta.resolve('./foo'))`
+  #[default]
+  ImportMetaResolve,
+  /// `fileURLToPath(new URL('./foo', import.meta.url))` for relative paths, `import.meta.resolve()` for the rest
+  Url,
+  /// Leave it as it is with a warning, for browsers, which have no file paths to resolve to nor node's `url`
+  Warn,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MainModule {
+  /// `import.meta.url === pathToFileURL(process.argv[1]).href`, works on every node version with ES modules
+  #[default]
+  ProcessArgv,
+  /// `import.meta.main`, for Deno, Bun and newer node versions
+  ImportMetaMain,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Dirname {
+  /// `const __filename = fileURLToPath(import.meta.url)` and `const __dirname = dirname(__filename)` at the top of
+  /// the file, works on every node version with ES modules
+  #[default]
+  Url,
+  /// `import.meta.dirname` and `import.meta.filename`, node 20.11 and newer
+  ImportMeta,
+  /// Leave them as they are with a warning, for browsers, which have no file paths
+  Warn,
+}
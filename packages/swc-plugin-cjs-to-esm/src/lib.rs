@@ -1,21 +1,35 @@
+// The code generated by `#[plugin_transform]` dereferences the raw pointers handed over by the host.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+#[cfg(feature = "api")]
+mod api;
+#[cfg(all(test, feature = "api"))]
+mod tests;
 mod utils;
 pub mod config;
+pub mod project;
+pub mod report;
+pub mod resolve;
 pub mod transform_cjs;
 pub mod visitors;
 
-use std::path::PathBuf;
-
 use swc_core::{
-    common::{chain, Mark}, 
-    ecma::{
-        transforms::{base::resolver, testing::test_fixture}, 
-        ast::Program,
-        visit::{Fold, FoldWith},
-    }, 
-    plugin::{plugin_transform, proxies::TransformPluginProgramMetadata},
+    ecma::{ast::Program, visit::FoldWith},
+    plugin::{
+        errors::HANDLER,
+        metadata::TransformPluginMetadataContextKind,
+        plugin_transform,
+        proxies::TransformPluginProgramMetadata,
+    },
 };
+#[cfg(feature = "api")]
+pub use crate::api::{syntax_for_path, transform_source, Diagnostic, Error, Options, Output, Result, Severity};
 
-use crate::transform_cjs::cjs_to_esm;
+use crate::{
+    config::CjsToEsmConfig,
+    report::SharedReport,
+    transform_cjs::{cjs_to_esm, emit_report_comment, resolve_relative_specifiers},
+};
 
 /// An example plugin function with macro support.
 /// `plugin_transform` macro interop pointers into deserialized structs, as well
@@ -33,43 +47,31 @@ use crate::transform_cjs::cjs_to_esm;
 /// This requires manual handling of serialization / deserialization from ptrs.
 /// Refer swc_plugin_macro to see how does it work internally.
 #[plugin_transform]
-pub fn process_transform(program: Program, _metadata: TransformPluginProgramMetadata) -> Program {
-    program.fold_with(&mut cjs_to_esm())
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+    let config = metadata.get_transform_plugin_config().map(|config| serde_json::from_str::<CjsToEsmConfig>(&config));
+    let config = match config {
+        Some(Ok(config)) => config,
+        None => Default::default(),
+        // Reported like any other error of the build, converting with options that weren't asked for would be worse
+        Some(Err(err)) => {
+            HANDLER.with(|handler| handler.err(&format!("invalid config for swc-plugin-cjs-to-esm: {}", err)));
+            return program;
+        },
+    };
+    let report = SharedReport::default();
+    let mut program = program.fold_with(&mut cjs_to_esm(metadata.comments, report.clone(), None, &config));
+    // Skipped files are left as they are, report comment included
+    let convert = !report.borrow().skipped;
+    if let (true, Some(filename)) = (convert && config.resolve(), metadata.get_context(&TransformPluginMetadataContextKind::Filename)) {
+        program = program.fold_with(&mut resolve_relative_specifiers(
+            filename.into(),
+            config.extension.clone(),
+            config.json_attributes(),
+            report.clone(),
+        ));
+    }
+    if convert && config.report {
+        program = program.fold_with(&mut emit_report_comment(metadata.comments, report));
+    }
+    program
 }
-
-// An example to test plugin transform.
-// Recommended strategy to test plugin's transform is verify
-// the Visitor's behavior, instead of trying to run `process_transform` with mocks
-// unless explicitly required to do so.
-// test!(
-//     Default::default(),
-//     |_| as_folder(TransformVisitor),
-//     boo,
-//     // Input codes
-//     r#"console.log("transform");"#,
-//     // Output codes after transformed with plugin
-//     r#"console.log("transform");"#
-// );
-
-fn _tr() -> impl Fold {
-    let unresolved_mark = Mark::new();
-    let top_level_mark = Mark::new();
-
-    chain!(
-        resolver(unresolved_mark, top_level_mark, false),
-        cjs_to_esm()
-    )
-}
-
-// TODO: Not sure how to do fixtures yet but this will be pretty useful
-
-#[testing::fixture("tests/fixtures/**/input.js")]
-fn _exec(input: PathBuf) {
-    let output = input.with_file_name("output.js");
-    test_fixture(
-        Default::default(),
-        &|_t| _tr(),
-        &input,
-        &output,
-    );
-}
\ No newline at end of file
@@ -0,0 +1,240 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use swc_core::ecma::ast::*;
+// Scanning parses the files itself
+#[cfg(feature = "api")]
+use swc_core::{
+    common::{chain, comments::SingleThreadedComments, sync::Lrc, FileName, Mark, SourceMap, GLOBALS},
+    ecma::{
+        parser::{Parser, StringInput},
+        transforms::base::resolver,
+        visit::FoldWith,
+    },
+};
+
+use crate::resolve::{normalize_path, resolve_relative};
+#[cfg(feature = "api")]
+use crate::{api::syntax_for_path, config::CjsToEsmConfig, transform_cjs::cjs_to_esm};
+
+/**
+    What a module exports once it has been converted.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleExports {
+    pub named: BTreeSet<String>,
+    /// `module.exports` could not be split up and ended up as `export default`
+    pub default: bool,
+}
+
+impl ModuleExports {
+    /**
+        Reads the exports of an already converted module.
+     */
+    pub fn from_module(m: &Module) -> Self {
+        let mut exports = ModuleExports::default();
+        for item in &m.body {
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(..) | ModuleDecl::ExportDefaultDecl(..)) => {
+                    exports.default = true;
+                },
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => match decl {
+                    Decl::Var(var) => {
+                        for decl in &var.decls {
+                            if let Pat::Ident(name) = &decl.name {
+                                exports.named.insert(name.sym.to_string());
+                            }
+                        }
+                    },
+                    Decl::Fn(FnDecl { ident, .. }) | Decl::Class(ClassDecl { ident, .. }) => {
+                        exports.named.insert(ident.sym.to_string());
+                    },
+                    _ => {},
+                },
+                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport { specifiers, .. })) => {
+                    for specifier in specifiers {
+                        if let ExportSpecifier::Named(ExportNamedSpecifier { orig, exported, .. }) = specifier {
+                            let name = match exported.as_ref().unwrap_or(orig) {
+                                ModuleExportName::Ident(ident) => ident.sym.to_string(),
+                                ModuleExportName::Str(str) => str.value.to_string(),
+                            };
+                            if name == "default" {
+                                exports.default = true;
+                            } else {
+                                exports.named.insert(name);
+                            }
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+        exports
+    }
+}
+
+/**
+    How a required module should be imported.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interop<'a> {
+    /// What `require` returned is the default export: files of the project that ended up with `export default`,
+    /// and CommonJS packages, whose `module.exports` node hands out as the default export
+    Default,
+    /// A file of the project with known named exports
+    Named(&'a ModuleExports),
+    /// ES module packages and anything unknown, imported the way the visitors always did
+    Namespace,
+}
+
+/**
+    The exports of every file in a project, keyed by path.
+    Built up front so that requires of one file can be converted based on what the required file exports.
+    Also keeps track of which packages in `node_modules` are ES modules.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct ExportTable {
+    modules: HashMap<PathBuf, ModuleExports>,
+    // package.json of a package, to whether it is an ES module. `None` when there is no package.json
+    packages: RefCell<HashMap<PathBuf, Option<bool>>>,
+}
+
+impl ExportTable {
+    /**
+        Converts every file with `config`, the one the files are converted with afterwards, and records what it ends
+        up exporting. Files that can't be read or parsed are left out, requires of them are converted as if there was
+        no table.
+     */
+    #[cfg(feature = "api")]
+    pub fn scan(files: &[PathBuf], config: &CjsToEsmConfig) -> Self {
+        let mut table = ExportTable::default();
+        for file in files {
+            let src = std::fs::read_to_string(file).ok();
+            if let Some(exports) = src.and_then(|src| exports_of_source(&src, file, config)) {
+                table.insert(file, exports);
+            }
+        }
+        table
+    }
+
+    pub fn insert(&mut self, path: &Path, exports: ModuleExports) {
+        self.modules.insert(normalize_path(path), exports);
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&ModuleExports> {
+        self.modules.get(&normalize_path(path))
+    }
+
+    /**
+        Looks up what `specifier`, as required from `importer`, exports.
+        Only relative specifiers are looked up, with the same extensions node would try.
+     */
+    pub fn lookup(&self, importer: &Path, specifier: &str) -> Option<&ModuleExports> {
+        resolve_relative(importer, specifier, |path| self.modules.contains_key(path))
+            .and_then(|path| self.modules.get(&path))
+    }
+
+    /**
+        Whether the package a bare specifier points to is an ES module, going by the `package.json` of the closest
+        `node_modules/<package>` above `importer`. `None` for builtins and packages that aren't installed.
+     */
+    pub fn is_esm_package(&self, importer: &Path, specifier: &str) -> Option<bool> {
+        let name = package_name(specifier)?;
+        importer.ancestors().skip(1).find_map(|dir| {
+            let package_json = dir.join("node_modules").join(name).join("package.json");
+            *self
+                .packages
+                .borrow_mut()
+                .entry(package_json.clone())
+                .or_insert_with(|| {
+                    let json = std::fs::read_to_string(&package_json).ok()?;
+                    let json: serde_json::Value = serde_json::from_str(&json).ok()?;
+                    Some(is_esm_package_json(&json))
+                })
+        })
+    }
+
+    /**
+        How `specifier`, as required from `importer`, should be imported.
+     */
+    pub fn interop(&self, importer: &Path, specifier: &str) -> Interop<'_> {
+        if let Some(exports) = self.lookup(importer, specifier) {
+            return if exports.default { Interop::Default } else { Interop::Named(exports) };
+        }
+        match self.is_esm_package(importer, specifier) {
+            Some(false) => Interop::Default,
+            _ => Interop::Namespace,
+        }
+    }
+}
+
+/**
+    The file being converted as part of a project, gives the visitors access to the exports of the files it requires.
+ */
+#[derive(Debug, Clone)]
+pub struct ProjectFile {
+    pub exports: Rc<ExportTable>,
+    pub path: PathBuf,
+}
+
+impl ProjectFile {
+    pub fn new(exports: Rc<ExportTable>, path: impl Into<PathBuf>) -> Self {
+        Self { exports, path: path.into() }
+    }
+
+    pub fn interop(&self, specifier: &str) -> Interop<'_> {
+        self.exports.interop(&self.path, specifier)
+    }
+}
+
+/**
+    The package a bare specifier imports from, ie `lodash` for `lodash/fp` and `@babel/core` for `@babel/core/lib/x`.
+    Relative and absolute specifiers, urls and node builtins (`node:fs`) have none.
+ */
+fn package_name(specifier: &str) -> Option<&str> {
+    if specifier.starts_with('.') || specifier.starts_with('/') || specifier.contains(':') {
+        return None;
+    }
+    let mut end = specifier.find('/').unwrap_or(specifier.len());
+    if specifier.starts_with('@') && end < specifier.len() {
+        end = specifier[end + 1..].find('/').map_or(specifier.len(), |i| end + 1 + i);
+    }
+    Some(&specifier[..end])
+}
+
+/**
+    Node treats a package as an ES module with `"type": "module"`, and prefers an ES module entry when its `exports`
+    have an `import` condition or its `main` is a `.mjs` file.
+ */
+fn is_esm_package_json(json: &serde_json::Value) -> bool {
+    fn has_import_condition(exports: &serde_json::Value) -> bool {
+        match exports {
+            serde_json::Value::Object(map) => map.contains_key("import") || map.values().any(has_import_condition),
+            serde_json::Value::Array(items) => items.iter().any(has_import_condition),
+            _ => false,
+        }
+    }
+    json["type"] == "module"
+        || has_import_condition(&json["exports"])
+        || json["main"].as_str().is_some_and(|main| main.ends_with(".mjs"))
+}
+
+#[cfg(feature = "api")]
+fn exports_of_source(src: &str, path: &Path, config: &CjsToEsmConfig) -> Option<ModuleExports> {
+    GLOBALS.set(&Default::default(), || {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Real(path.to_owned()), src.into());
+        let module = Parser::new(syntax_for_path(&path.to_string_lossy()), StringInput::from(&*fm), None)
+            .parse_module()
+            .ok()?
+            .fold_with(&mut chain!(
+                resolver(Mark::new(), Mark::new(), syntax_for_path(&path.to_string_lossy()).typescript()),
+                cjs_to_esm(None::<SingleThreadedComments>, Default::default(), None, config),
+            ));
+        Some(ModuleExports::from_module(&module))
+    })
+}
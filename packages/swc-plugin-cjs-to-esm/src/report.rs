@@ -0,0 +1,113 @@
+use std::{cell::RefCell, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+use swc_core::common::Span;
+
+/**
+    Marker that starts the comment the report is emitted as, ie `// cjs-to-esm-report {...}`.
+    A line comment is used so the report always sits on a line of its own.
+ */
+pub const REPORT_COMMENT_PREFIX: &str = "cjs-to-esm-report";
+
+/**
+    Summary of what the transform did to a single file.
+    Serialized as camelCase json so the mdnzr cli can aggregate it into a migration summary.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionReport {
+    pub imports: ImportCounts,
+    pub exports: ExportCounts,
+    /// `require` calls that could only be converted to a namespace import under a generated name
+    pub fallbacks: usize,
+    /// `module.exports = ...` assignments that ended up as `export default`
+    pub default_exports: usize,
+    /// `require` references left in the output, ie requires inside functions or with computed paths.
+    /// The ones kept with a `// cjs-to-esm-ignore-next-line` comment aren't counted
+    pub remaining_requires: usize,
+    /// `module` and `exports` references left in the output
+    pub remaining_module_refs: usize,
+    /// Things that need a closer look after the migration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// The file was left as it is, since it is an ES module already or opted out with `cjs-to-esm-disable`.
+    /// Skipped files get no report comment, so this never makes it into the json
+    #[serde(skip)]
+    pub skipped: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warning {
+    pub message: String,
+    /// Only meaningful together with the source map of the file, so it is left out of the json
+    #[serde(skip)]
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCounts {
+    /// `require('foo');` to `import 'foo';`
+    pub side_effect: usize,
+    /// `const foo = require('foo');` to `import * as foo from 'foo';`
+    pub namespace: usize,
+    /// `const foo = require('./foo');` to `import foo from './foo';`, when `./foo` is known to have a default export
+    pub default: usize,
+    /// `const { foo } = require('foo');` to `import { foo } from 'foo';`
+    pub named: usize,
+    /// ``require(`./locales/${lang}.json`)`` to an eager `import.meta.glob()`, with `globImports`
+    pub glob: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCounts {
+    /// Names exported through `export { foo }`
+    pub named: usize,
+    /// `exports.foo = 123;` to `export const foo = 123;`
+    pub declarations: usize,
+}
+
+/**
+    The report is filled in by every visitor of the chain, so they all hold on to the same one.
+ */
+pub type SharedReport = Rc<RefCell<ConversionReport>>;
+
+impl ConversionReport {
+    /**
+        Renders the report as the text of a line comment, ie `cjs-to-esm-report {"imports":...}`.
+     */
+    pub fn to_comment_text(&self) -> String {
+        format!(" {} {}", REPORT_COMMENT_PREFIX, serde_json::to_string(self).unwrap())
+    }
+
+    pub fn warn(&mut self, span: Span, message: impl Into<String>) {
+        self.warnings.push(Warning { message: message.into(), span });
+    }
+
+    /**
+        Adds the numbers of another report to this one, used to total up the reports of many files.
+        Warnings are left out since they only make sense next to the file they came from.
+     */
+    pub fn merge(&mut self, other: &ConversionReport) {
+        self.imports.side_effect += other.imports.side_effect;
+        self.imports.namespace += other.imports.namespace;
+        self.imports.default += other.imports.default;
+        self.imports.named += other.imports.named;
+        self.imports.glob += other.imports.glob;
+        self.exports.named += other.exports.named;
+        self.exports.declarations += other.exports.declarations;
+        self.fallbacks += other.fallbacks;
+        self.default_exports += other.default_exports;
+        self.remaining_requires += other.remaining_requires;
+        self.remaining_module_refs += other.remaining_module_refs;
+    }
+
+    /**
+        Reads a report back from the text of a comment made by `to_comment_text()`.
+     */
+    pub fn from_comment_text(text: &str) -> Option<Self> {
+        let json = text.trim().strip_prefix(REPORT_COMMENT_PREFIX)?;
+        serde_json::from_str(json.trim()).ok()
+    }
+}
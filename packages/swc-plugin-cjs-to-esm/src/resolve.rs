@@ -0,0 +1,90 @@
+use std::path::{Component, Path, PathBuf};
+
+/**
+    Resolves a relative specifier the way `require` does, trying the path as is, then with `.js`, `.cjs` and `.json`
+    appended and then as a directory with an `index.js`. `.jsx` files are tried after the ones node knows about,
+    like bundlers do. Bare specifiers are not resolved.
+ */
+pub fn resolve_relative(importer: &Path, specifier: &str, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    if !is_relative(specifier) {
+        return None;
+    }
+    let base = normalize_path(&importer.parent().unwrap_or(Path::new("")).join(specifier));
+    let with_suffix = |suffix: &str| {
+        let mut path = base.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    [
+        base.clone(),
+        with_suffix(".js"),
+        with_suffix(".cjs"),
+        with_suffix(".json"),
+        with_suffix(".jsx"),
+        base.join("index.js"),
+        base.join("index.cjs"),
+        base.join("index.jsx"),
+    ]
+    .into_iter()
+    .find(|path| exists(path))
+}
+
+/**
+    Whether the specifier is relative to the importing file, ie `./foo` or `../foo`.
+ */
+pub fn is_relative(specifier: &str) -> bool {
+    specifier.starts_with("./") || specifier.starts_with("../")
+}
+
+/**
+    Whether the specifier points at a JSON file, which ESM only hands out as a default export.
+ */
+pub fn is_json(specifier: &str) -> bool {
+    specifier.ends_with(".json")
+}
+
+/**
+    Cleans up `.` and `..` in a path without touching the filesystem.
+ */
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(..))) => {
+                normalized.pop();
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/**
+    Turns a relative specifier into one that points at a file, ie `./foo` to `./foo.js` or `./foo/index.js`,
+    since ESM doesn't try extensions or directory indexes.
+    With an `extension` like `.mjs`, specifiers of `.js` and `.jsx` files are changed to the extension the file
+    will have once converted, `.cjs` files aren't converted and keep theirs.
+    Returns `None` for bare specifiers and specifiers that don't resolve.
+ */
+pub fn rewrite_specifier(
+    importer: &Path,
+    specifier: &str,
+    extension: Option<&str>,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<String> {
+    let resolved = resolve_relative(importer, specifier, exists)?;
+    let base = normalize_path(&importer.parent().unwrap_or(Path::new("")).join(specifier));
+    // Whatever resolution added to the specifier, ie `.js` or `/index.js`
+    let suffix = resolved.to_string_lossy().strip_prefix(&*base.to_string_lossy())?.to_owned();
+    let mut rewritten = format!("{}{}", specifier.trim_end_matches('/'), suffix);
+
+    let is_js = matches!(resolved.extension().and_then(|ext| ext.to_str()), Some("js" | "jsx"));
+    if let (true, Some(extension)) = (is_js, extension) {
+        let stem_len = rewritten.len() - resolved.extension().map_or(0, |ext| ext.len() + 1);
+        rewritten.truncate(stem_len);
+        rewritten.push('.');
+        rewritten.push_str(extension.trim_start_matches('.'));
+    }
+    Some(rewritten)
+}
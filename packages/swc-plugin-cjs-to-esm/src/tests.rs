@@ -0,0 +1,298 @@
+use std::{path::PathBuf, rc::Rc};
+
+use swc_core::{
+    common::{chain, comments::SingleThreadedComments, sync::Lrc, BytePos, FileName, LineCol, Mark, SourceMap}, 
+    ecma::{
+        transforms::{base::resolver, testing::test_fixture}, 
+        ast::Module,
+        codegen::{text_writer::JsWriter, Emitter},
+        parser::{Parser, StringInput, Syntax},
+        visit::{Fold, FoldWith},
+    }, 
+};
+use testing::NormalizedOutput;
+
+use crate::{
+    config::{CjsToEsmConfig, QuotesStyle},
+    project::ExportTable,
+    report::{ConversionReport, SharedReport},
+    syntax_for_path, transform_source, Options,
+    transform_cjs::{cjs_to_esm, esm_to_cjs},
+};
+
+// An example to test plugin transform.
+// Recommended strategy to test plugin's transform is verify
+// the Visitor's behavior, instead of trying to run `process_transform` with mocks
+// unless explicitly required to do so.
+// test!(
+//     Default::default(),
+//     |_| as_folder(TransformVisitor),
+//     boo,
+//     // Input codes
+//     r#"console.log("transform");"#,
+//     // Output codes after transformed with plugin
+//     r#"console.log("transform");"#
+// );
+
+fn _tr(comments: Rc<SingleThreadedComments>, report: SharedReport, config: &CjsToEsmConfig) -> impl Fold {
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+
+    chain!(
+        resolver(unresolved_mark, top_level_mark, false),
+        cjs_to_esm(Some(comments), report, None, config)
+    )
+}
+
+// The syntax and the name of the output are picked by the extension of the input, ie `input.jsx` and `output.jsx`.
+// The plugin config can be set with a `config.json` next to the input.
+#[testing::fixture("tests/fixtures/**/input.*")]
+fn _exec(input: PathBuf) {
+    let extension = input.extension().unwrap().to_string_lossy();
+    let output = input.with_file_name(format!("output.{}", extension));
+    let config = std::fs::read_to_string(input.with_file_name("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    test_fixture(
+        syntax_for_path(&input.to_string_lossy()),
+        &|t| _tr(t.comments.clone(), Default::default(), &config),
+        &input,
+        &output,
+    );
+}
+
+// Converts ES modules back to CommonJS.
+#[testing::fixture("tests/esm_to_cjs/**/input.js")]
+fn _exec_esm_to_cjs(input: PathBuf) {
+    let output = input.with_file_name("output.js");
+    test_fixture(
+        Default::default(),
+        &|_| esm_to_cjs(Default::default()),
+        &input,
+        &output,
+    );
+}
+
+// Converting the output of a fixture back to CommonJS and then to ESM again with the same config should give
+// the same output, apart from the order of the imports and of the one line exports at the end, which come back
+// in the order of the assignments to `exports` rather than in the order they were grouped in. Exported function
+// and class declarations come back as `export { foo }` after the declaration, which rules out the TypeScript
+// fixtures, the default exported function of export_nested_binding and the fixtures that start out partially
+// migrated.
+// ES modules are left as they are, so converting them back to CommonJS is a one way trip.
+#[testing::fixture("tests/fixtures/**/output.js*", exclude("export_nested_binding|partially_migrated|already_esm"))]
+fn _exec_round_trip(output: PathBuf) {
+    let config = std::fs::read_to_string(output.with_file_name("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    let print = |cm: Lrc<SourceMap>, module: &Module| {
+        let code = _print(cm, module);
+        let (mut imports, mut rest): (Vec<&str>, Vec<&str>) = code.lines().partition(|line| line.starts_with("import "));
+        imports.sort_unstable();
+        let exports = rest.iter().rev().take_while(|line| line.starts_with("export ") && line.ends_with(';')).count();
+        let at = rest.len() - exports;
+        rest[at..].sort_unstable();
+        format!("{}\n{}", imports.join("\n"), rest.join("\n"))
+    };
+    testing::run_test2(false, |cm, _handler| {
+        let fm = cm.load_file(&output).unwrap();
+        let comments = Rc::new(SingleThreadedComments::default());
+        let module = Parser::new(syntax_for_path(&output.to_string_lossy()), StringInput::from(&*fm), Some(&comments))
+            .parse_module()
+            .unwrap();
+        let expected = print(cm.clone(), &module);
+        let module = module
+            .fold_with(&mut esm_to_cjs(Default::default()))
+            .fold_with(&mut _tr(comments.clone(), Default::default(), &config));
+        assert_eq!(print(cm.clone(), &module), expected);
+        Ok(())
+    })
+    .unwrap();
+}
+
+// Running the transform over its own output has to leave it as it is, since files are migrated a bit at a time.
+#[testing::fixture("tests/fixtures/**/input.*")]
+fn _exec_idempotent(input: PathBuf) {
+    let config = std::fs::read_to_string(input.with_file_name("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    testing::run_test2(false, |cm, _handler| {
+        let syntax = syntax_for_path(&input.to_string_lossy());
+        let print = |src: String, transform: bool| {
+            let fm = cm.new_source_file(FileName::Anon, src);
+            let comments = Rc::new(SingleThreadedComments::default());
+            let mut module = Parser::new(syntax, StringInput::from(&*fm), Some(&comments)).parse_module().unwrap();
+            if transform {
+                module = module.fold_with(&mut _tr(comments.clone(), Default::default(), &config));
+            }
+            // With the comments, which have the pragmas in them
+            _print_with_comments(cm.clone(), &module, Some(&comments))
+        };
+        let once = print(std::fs::read_to_string(&input).unwrap(), true);
+        // Printed again without the transform, since printing JSX isn't stable on its own
+        assert_eq!(print(once.clone(), true), print(once, false));
+        Ok(())
+    })
+    .unwrap();
+}
+
+// Code without any `require` or `module.exports` has to come out the same as it went in.
+#[testing::fixture("tests/passthrough/**/*.js")]
+fn _exec_passthrough(input: PathBuf) {
+    testing::run_test2(false, |cm, _handler| {
+        let fm = cm.load_file(&input).unwrap();
+        let comments = Rc::new(SingleThreadedComments::default());
+        let module = Parser::new(Syntax::default(), StringInput::from(&*fm), Some(&comments))
+            .parse_module()
+            .unwrap();
+        let expected = _print(cm.clone(), &module);
+        let module = module.fold_with(&mut _tr(comments.clone(), Default::default(), &Default::default()));
+        assert_eq!(_print(cm.clone(), &module), expected);
+        Ok(())
+    })
+    .unwrap();
+}
+
+// Skipped files, ES modules and files with a `cjs-to-esm-disable` comment, come back byte for byte,
+// whatever the options that would change the output otherwise.
+#[testing::fixture("tests/untouched/*.js")]
+fn _exec_untouched(input: PathBuf) {
+    let src = std::fs::read_to_string(&input).unwrap();
+    let options = Options {
+        source_map: true,
+        config: CjsToEsmConfig {
+            quotes_style: QuotesStyle::Single,
+            report: true,
+            resolve: Some(true),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let output = transform_source(&src, &input.to_string_lossy(), &options).unwrap();
+    assert!(output.report.skipped);
+    assert_eq!(output.code, src);
+    assert!(output.map.is_some());
+}
+
+fn _print(cm: Lrc<SourceMap>, module: &Module) -> String {
+    _print_with_comments(cm, module, None)
+}
+
+fn _print_with_comments(cm: Lrc<SourceMap>, module: &Module, comments: Option<&SingleThreadedComments>) -> String {
+    let mut code = vec![];
+    Emitter {
+        cfg: Default::default(),
+        cm: cm.clone(),
+        comments: comments.map(|comments| comments as _),
+        wr: JsWriter::new(cm, "\n", &mut code, None),
+    }
+    .emit_module(module)
+    .unwrap();
+    String::from_utf8(code).unwrap()
+}
+
+// Checks that the generated code maps back to the `require` calls and `module.exports` assignments it came from.
+// Each line of `mappings.txt` is `<generated line:col> <generated code> -> <original line:col> <original code>`.
+#[testing::fixture("tests/source_maps/**/input.js")]
+fn _exec_source_map(input: PathBuf) {
+    let output = input.with_file_name("mappings.txt");
+    let mappings = testing::run_test2(false, |cm, _handler| {
+        let fm = cm.load_file(&input).unwrap();
+        let comments = Rc::new(SingleThreadedComments::default());
+        let module = Parser::new(Syntax::default(), StringInput::from(&*fm), Some(&comments))
+            .parse_module()
+            .unwrap()
+            .fold_with(&mut _tr(comments.clone(), Default::default(), &Default::default()));
+
+        let mut code = vec![];
+        let mut src_map: Vec<(BytePos, LineCol)> = vec![];
+        Emitter {
+            cfg: Default::default(),
+            cm: cm.clone(),
+            comments: Some(&comments),
+            wr: JsWriter::new(cm.clone(), "\n", &mut code, Some(&mut src_map)),
+        }
+        .emit_module(&module)
+        .unwrap();
+        let code = String::from_utf8(code).unwrap();
+        let src_map = cm.build_source_map(&src_map);
+
+        let word_at = |text: &str, line: u32, col: u32| -> String {
+            text.lines()
+                .nth(line as usize)
+                .map(|line| line.chars().skip(col as usize).take_while(|c| !c.is_whitespace()).collect())
+                .unwrap_or_default()
+        };
+        let mut mappings = String::new();
+        for token in src_map.tokens() {
+            mappings.push_str(&format!(
+                "{}:{} {} -> {}:{} {}\n",
+                token.get_dst_line() + 1,
+                token.get_dst_col(),
+                word_at(&code, token.get_dst_line(), token.get_dst_col()),
+                token.get_src_line() + 1,
+                token.get_src_col(),
+                word_at(&fm.src, token.get_src_line(), token.get_src_col()),
+            ));
+        }
+        Ok(mappings)
+    })
+    .unwrap();
+
+    NormalizedOutput::from(mappings).compare_to_file(output).unwrap();
+}
+
+// Checks the conversion report against `report.json`, and that it makes it into the comment mdnzr reads it from.
+// The plugin config can be set with a `config.json` next to the input.
+#[testing::fixture("tests/reports/**/input.js")]
+fn _exec_report(input: PathBuf) {
+    let output = input.with_file_name("report.json");
+    let config: CjsToEsmConfig = std::fs::read_to_string(input.with_file_name("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    let options = Options {
+        config: CjsToEsmConfig { report: true, ..config },
+        ..Default::default()
+    };
+    let result = transform_source(&std::fs::read_to_string(&input).unwrap(), "input.js", &options).unwrap();
+
+    let comment = result.code.lines().next().unwrap();
+    let emitted = comment
+        .strip_prefix("//")
+        .and_then(ConversionReport::from_comment_text);
+    // Spans of warnings don't make it into the comment, so the json is compared instead
+    assert_eq!(
+        emitted.map(|report| serde_json::to_value(report).unwrap()),
+        Some(serde_json::to_value(&result.report).unwrap()),
+    );
+
+    let mut report = serde_json::to_string_pretty(&result.report).unwrap();
+    for diagnostic in &result.diagnostics {
+        report.push_str(&format!("\n{}", diagnostic));
+    }
+    NormalizedOutput::from(report).compare_to_file(output).unwrap();
+}
+
+// Converts all files of `tests/projects/<name>/input` together and compares them to the files in `output`.
+// The plugin config can be set with a `config.json` next to `input`.
+#[testing::fixture("tests/projects/*/input")]
+fn _exec_project(input: PathBuf) {
+    let files: Vec<PathBuf> = glob::glob(&format!("{}/**/*.js", input.display()))
+        .unwrap()
+        .flatten()
+        .filter(|file| !file.components().any(|c| c.as_os_str() == "node_modules"))
+        .collect();
+    let config: CjsToEsmConfig = std::fs::read_to_string(input.with_file_name("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    let options = Options {
+        project: Some(Rc::new(ExportTable::scan(&files, &config))),
+        config,
+        ..Default::default()
+    };
+    for file in files {
+        let result = transform_source(&std::fs::read_to_string(&file).unwrap(), &file.to_string_lossy(), &options).unwrap();
+        let output = input.with_file_name("output").join(file.strip_prefix(&input).unwrap());
+        NormalizedOutput::from(result.code).compare_to_file(output).unwrap();
+    }
+}
@@ -1,7 +1,17 @@
-use swc_core::common::chain;
-use swc_core::ecma::visit::{Fold, as_folder};
+use std::path::PathBuf;
 
-use crate::visitors::*;
+use swc_core::common::{chain, comments::Comments};
+use swc_core::ecma::{
+    transforms::base::pass::Optional,
+    visit::{Fold, as_folder},
+};
+
+use crate::{
+    config::{CjsToEsmConfig, Dirname, MainModule, QuotesStyle, RequireResolve, SideEffects},
+    project::ProjectFile,
+    report::SharedReport,
+    visitors::*,
+};
 
 /**
     Transforms require expression statements:
@@ -16,9 +26,9 @@ use crate::visitors::*;
     import 'foo';
     ```
  */
-pub fn transform_require_expr_stmt() -> impl Fold {
+pub fn transform_require_expr_stmt<C: Comments>(comments: Option<C>, report: SharedReport) -> impl Fold {
     // Why does this require initialization? Do I need a default?
-    as_folder(TransformRequireStatementVistor::new())
+    as_folder(TransformRequireStatementVistor::new(comments, report))
 }
 
 /**
@@ -34,8 +44,12 @@ pub fn transform_require_expr_stmt() -> impl Fold {
     import * as foo from 'foo';
     ```
  */
-pub fn transform_require_ident_to_import() -> impl Fold {
-    as_folder(TransformRequireIdentVisitor::new())
+pub fn transform_require_ident_to_import<C: Comments>(
+    comments: Option<C>,
+    report: SharedReport,
+    project: Option<ProjectFile>,
+) -> impl Fold {
+    as_folder(TransformRequireIdentVisitor::new(comments, report, project))
 }
 
 /**
@@ -49,9 +63,30 @@ pub fn transform_require_ident_to_import() -> impl Fold {
     ```js
     import { foo, bar as baz } from 'foo';
     ```
+
+    Nested patterns are imported under a generated name and destructured locally,
+    while rest elements and defaults destructure from a namespace import instead.
+
+    ```js
+    const { foo: { bar } } = require('foo');
+    const { baz, ...rest } = require('baz');
+    ```
+
+    to
+
+    ```js
+    import { foo as _foo$1 } from 'foo';
+    import * as _baz$2 from 'baz';
+    const { bar } = _foo$1;
+    const { baz, ...rest } = _baz$2;
+    ```
  */
-pub fn transform_require_pure_destructure_to_named_imports() -> impl Fold {
-    as_folder(TransformPureDestructuredRequireVisitor::new())
+pub fn transform_require_pure_destructure_to_named_imports<C: Comments>(
+    comments: Option<C>,
+    report: SharedReport,
+    project: Option<ProjectFile>,
+) -> impl Fold {
+    as_folder(TransformPureDestructuredRequireVisitor::new(comments, report, project))
 }
 
 /**
@@ -71,8 +106,8 @@ pub fn transform_require_pure_destructure_to_named_imports() -> impl Fold {
     const {a, b, c = b} = mod$2;
     ```
  */
-pub fn transform_require_expression_to_import() -> impl Fold {
-    as_folder(TransformRequireFallback::new())
+pub fn transform_require_expression_to_import(report: SharedReport, project: Option<ProjectFile>) -> impl Fold {
+    as_folder(TransformRequireFallback::new(report, project))
 }
 
 /**
@@ -89,8 +124,8 @@ pub fn transform_require_expression_to_import() -> impl Fold {
     export default foo;
     ```
  */
-pub fn transform_module_exports_ident_to_named_export() -> impl Fold {
-    as_folder(TransformModuleExportsIdentVisitor::new())
+pub fn transform_module_exports_ident_to_named_export(report: SharedReport) -> impl Fold {
+    as_folder(TransformModuleExportsIdentVisitor::new(report))
 }
 
 /**
@@ -106,8 +141,8 @@ pub fn transform_module_exports_ident_to_named_export() -> impl Fold {
     export {foo as bar, baz};
     ```
  */
-pub fn transform_module_exports_object() -> impl Fold {
-    as_folder(TransformExportDefaultObject::new())
+pub fn transform_module_exports_object(report: SharedReport) -> impl Fold {
+    as_folder(TransformExportDefaultObject::new(report))
 }
 
 /**
@@ -123,8 +158,8 @@ pub fn transform_module_exports_object() -> impl Fold {
     export const foo = 123;
     ```
  */
-pub fn transform_module_exports_named_expression() -> impl Fold {
-    as_folder(TransformModuleExportsNamedExprVisitor::new())
+pub fn transform_module_exports_named_expression(report: SharedReport) -> impl Fold {
+    as_folder(TransformModuleExportsNamedExprVisitor::new(report))
 }
 
 /**
@@ -142,44 +177,408 @@ pub fn transform_module_exports_named_expression() -> impl Fold {
 
     And emits a warning that this file has as default export.
  */
-pub fn transform_module_default_export() -> impl Fold {
-    as_folder(TransformModuleDefaultExport::new())
+pub fn transform_module_default_export(report: SharedReport) -> impl Fold {
+    as_folder(TransformModuleDefaultExport::new(report))
+}
+
+/**
+    Transforms the TypeScript forms of `require` and `module.exports`
+
+    ```ts
+    import foo = require('foo');
+    import type Bar = require('bar');
+    export = foo;
+    ```
+
+    to their plain forms, which the other transforms take care of.
+    Imports only used as types are converted right away.
+
+    ```ts
+    const foo = require('foo');
+    import type * as Bar from 'bar';
+    module.exports = foo;
+    ```
+ */
+pub fn transform_typescript_module_syntax(report: SharedReport) -> impl Fold {
+    as_folder(TransformTsModuleSyntax::new(report))
+}
+
+/**
+    Splits declarations with a `require` into one statement per declarator
+
+    ```js
+    const foo = require('foo'), bar = 1;
+    ```
+
+    to
+
+    ```js
+    const foo = require('foo');
+    const bar = 1;
+    ```
+
+    Declarators after one with side effects stay together, along with their requires. Those are warned about,
+    or made with `createRequire()` by `check_side_effect_order()` with `SideEffects::CreateRequire`.
+ */
+pub fn split_require_declarations(strategy: SideEffects, report: SharedReport) -> impl Fold {
+    as_folder(SplitRequireDeclarations::new(strategy, report))
+}
+
+/**
+    Finds requires that would be hoisted above code with side effects, and warns about them or keeps them as
+    `require` made with `createRequire()`, depending on `strategy`
+
+    ```js
+    process.env.DEBUG = '1';
+    const config = require('./config');
+    ```
+
+    to
+
+    ```js
+    import { createRequire } from 'module';
+    const _require$1 = createRequire(import.meta.url);
+    process.env.DEBUG = '1';
+    const config = _require$1('./config');
+    ```
+ */
+pub fn check_side_effect_order(strategy: SideEffects, report: SharedReport) -> impl Fold {
+    as_folder(CheckSideEffectOrder::new(strategy, report))
+}
+
+/**
+    Transforms `require.resolve()` calls
+
+    ```js
+    const path = require.resolve('./foo');
+    ```
+
+    to
+
+    ```js
+    import { fileURLToPath } from 'url';
+    const path = fileURLToPath(import.meta.resolve('./foo'));
+    ```
+
+    or `fileURLToPath(new URL('./foo', import.meta.url))` with `RequireResolve::Url`, and warns about
+    `require.cache`, `require.main` and `module.parent`.
+ */
+pub fn transform_require_resolve(strategy: RequireResolve, report: SharedReport) -> impl Fold {
+    as_folder(TransformRequireResolve::new(strategy, report))
+}
+
+/**
+    Transforms `__dirname` and `__filename`
+
+    ```js
+    const config = path.join(__dirname, 'config.json');
+    ```
+
+    to
+
+    ```js
+    import { fileURLToPath } from 'url';
+    import { dirname } from 'path';
+    const __filename = fileURLToPath(import.meta.url);
+    const __dirname = dirname(__filename);
+    const config = path.join(__dirname, 'config.json');
+    ```
+
+    or `import.meta.dirname` with `Dirname::ImportMeta`, and warns about them with `Dirname::Warn`.
+ */
+pub fn transform_dirname(strategy: Dirname, report: SharedReport) -> impl Fold {
+    as_folder(TransformDirname::new(strategy, report))
+}
+
+/**
+    Transforms the check for whether a file was run directly
+
+    ```js
+    if (require.main === module) {
+        main();
+    }
+    ```
+
+    to
+
+    ```js
+    import { pathToFileURL } from 'url';
+    if (import.meta.url === pathToFileURL(process.argv[1]).href) {
+        main();
+    }
+    ```
+
+    or `if (import.meta.main)` with `MainModule::ImportMetaMain`.
+ */
+pub fn transform_main_module_check(strategy: MainModule) -> impl Fold {
+    as_folder(TransformMainModuleCheck::new(strategy))
+}
+
+/**
+    Transforms requires of relative template literals
+
+    ```js
+    const messages = require(`./locales/messages.${lang}.json`);
+    ```
+
+    to a lookup in an eager glob import, which Vite replaces with imports of all the files that match.
+
+    ```js
+    const messages = import.meta.glob('./locales/messages.*.json', { eager: true })[`./locales/messages.${lang}.json`];
+    ```
+ */
+pub fn transform_template_require_to_glob(report: SharedReport) -> impl Fold {
+    as_folder(TransformTemplateRequireToGlob::new(report))
 }
 
 /**
    Transforms top-level cjs `require` statements to esm `import`s.
    This chains together several visitors to handle different types of `require` syntaxes.
  */
-pub fn transform_imports() -> impl Fold {
+pub fn transform_imports<C: Comments + Clone>(
+    comments: Option<C>,
+    report: SharedReport,
+    project: Option<ProjectFile>,
+    config: &CjsToEsmConfig,
+) -> impl Fold {
     chain!(
+        split_require_declarations(config.side_effects(), report.clone()),
+        check_side_effect_order(config.side_effects(), report.clone()),
         // TODO: Handle transformation of `require('foo').bar();` to `import * as _mod$a1 from 'foo'; foo$123.bar();`
-        transform_require_expr_stmt(),
-        transform_require_ident_to_import(),
-        transform_require_pure_destructure_to_named_imports(),
+        transform_require_expr_stmt(comments.clone(), report.clone()),
+        transform_require_ident_to_import(comments.clone(), report.clone(), project.clone()),
+        transform_require_pure_destructure_to_named_imports(comments, report.clone(), project.clone()),
         // TODO: Handle special case of const a = require('...').default
         // This is a fallback statement and should probably remain last. Handles all other unusual cases.
-        transform_require_expression_to_import(),
+        transform_require_expression_to_import(report.clone(), project),
+        // After the requires are converted, so the imports they add go next to an import of `url` from the file
+        transform_main_module_check(config.main_module()),
+        transform_require_resolve(config.require_resolve(), report.clone()),
+        transform_dirname(config.dirname(), report.clone()),
+        Optional::new(transform_template_require_to_glob(report), config.glob_imports()),
     )
 }
 
 /**
     Transforms top-level cjs `module.exports` (and `exports.`) to esm `export`s.
  */
-pub fn transform_exports() -> impl Fold {
+pub fn transform_exports(report: SharedReport) -> impl Fold {
     chain!(
-        transform_module_exports_ident_to_named_export(),
-        transform_module_exports_named_expression(),
-        transform_module_exports_object(),
-        transform_module_default_export(),
+        transform_module_exports_ident_to_named_export(report.clone()),
+        transform_module_exports_named_expression(report.clone()),
+        transform_module_exports_object(report.clone()),
+        transform_module_default_export(report),
     )
 }
 
 /**
     Transforms cjs require/module.exports to esm imports/exports.
+
+    Comments attached to converted statements are moved onto their replacements,
+    and comments leading the file stay at the top.
+    What got converted, and what was left behind, is tallied up in `report`.
+    When the file is converted as part of a `project`, requires of other files of the project
+    are converted based on what those files export.
+
+    ES modules are left as they are, and so are files that start with a `cjs-to-esm-disable` comment.
+    Statements and expressions after a `// cjs-to-esm-ignore-next-line` comment keep their `require`,
+    `module` and `exports`:
+
+    ```js
+    // cjs-to-esm-ignore-next-line
+    const addon = require('./addon.node');
+    ```
  */
-pub fn cjs_to_esm() -> impl Fold {
+pub fn cjs_to_esm<C: Comments + Clone>(
+    comments: Option<C>,
+    report: SharedReport,
+    project: Option<ProjectFile>,
+    config: &CjsToEsmConfig,
+) -> impl Fold {
+    as_folder(SkipUnconvertedCode::new(
+        comments.clone(),
+        report.clone(),
+        as_folder(PreserveHeaderComments::new(
+            comments.clone(),
+            chain!(
+                transform_typescript_module_syntax(report.clone()),
+                transform_imports(comments.clone(), report.clone(), project, config),
+                transform_exports(report.clone()),
+                sort_hoisted_imports(),
+                merge_imports(comments.clone()),
+                normalize_quotes(config.quotes_style),
+                Optional::new(add_json_import_attributes(), config.json_attributes()),
+                count_remaining_references(report),
+            ),
+        )),
+    ))
+}
+
+/**
+    Sorts the imports at the top of the file by where they came from, so required files are evaluated in the
+    same order as before.
+ */
+pub fn sort_hoisted_imports() -> impl Fold {
+    as_folder(SortHoistedImports)
+}
+
+/**
+    Merges imports of the same file, ie those converted from `require` with the ones that were there already
+
+    ```js
+    import { readFile } from 'fs';
+    const { writeFile } = require('fs');
+    ```
+
+    to
+
+    ```js
+    import { readFile, writeFile } from 'fs';
+    ```
+ */
+pub fn merge_imports<C: Comments>(comments: Option<C>) -> impl Fold {
+    as_folder(MergeImports::new(comments))
+}
+
+/**
+    Quotes the module specifiers of imports and exports the same way
+
+    ```js
+    import * as foo from "foo";
+    import { bar } from 'it\'s';
+    ```
+
+    to
+
+    ```js
+    import * as foo from 'foo';
+    import { bar } from "it's";
+    ```
+
+    with `QuotesStyle::Single` and `QuotesStyle::Double` respectively.
+ */
+pub fn normalize_quotes(style: QuotesStyle) -> impl Fold {
+    as_folder(NormalizeQuotes { style })
+}
+
+/**
+    Adds import attributes to imports of JSON files
+
+    ```js
+    import config from './config.json';
+    ```
+
+    to
+
+    ```js
+    import config from './config.json' with { type: 'json' };
+    ```
+
+    In the AST of this swc version the attributes are still called `asserts`, see `transform_source()` for how
+    they are printed.
+ */
+pub fn add_json_import_attributes() -> impl Fold {
+    as_folder(AddJsonImportAttributes)
+}
+
+/**
+    Counts the `require`, `module` and `exports` references that are still left in the file.
+ */
+pub fn count_remaining_references(report: SharedReport) -> impl Fold {
+    as_folder(CountRemainingReferences::new(report))
+}
+
+/**
+    Adds the conversion report as a comment at the top of the file, should run after `cjs_to_esm()`
+
+    ```js
+    // cjs-to-esm-report {"imports":{"sideEffect":0,"namespace":1,"named":0},...}
+    import * as foo from 'foo';
+    ```
+ */
+pub fn emit_report_comment<C: Comments>(comments: Option<C>, report: SharedReport) -> impl Fold {
+    as_folder(EmitReportComment::new(comments, report))
+}
+
+/**
+    Rewrites relative specifiers to the files they point to, which ESM requires.
+    `importer` is the path of the file being transformed, and `extension` the extension converted files will get.
+    With `json_attributes`, imports that turn out to be of JSON files get `with { type: 'json' }`.
+
+    ```js
+    import { foo } from './foo';
+    import * as bar from './bar';
+    import * as settings from './settings';
+    ```
+
+    to
+
+    ```js
+    import { foo } from './foo.mjs';
+    import * as bar from './bar/index.mjs';
+    import settings from './settings.json';
+    ```
+ */
+pub fn resolve_relative_specifiers(
+    importer: PathBuf,
+    extension: Option<String>,
+    json_attributes: bool,
+    report: SharedReport,
+) -> impl Fold {
+    chain!(
+        as_folder(ResolveRelativeSpecifiers::new(importer, extension, report)),
+        Optional::new(add_json_import_attributes(), json_attributes),
+    )
+}
+
+/**
+    Transforms imports back to `require` calls:
+
+    ```js
+    import * as foo from 'foo';
+    import { bar, baz as qux } from 'bar';
+    ```
+
+    to
+
+    ```js
+    const foo = require('foo');
+    const { bar, baz: qux } = require('bar');
+    ```
+ */
+pub fn transform_import_to_require(report: SharedReport) -> impl Fold {
+    as_folder(TransformImportToRequireVisitor::new(report))
+}
+
+/**
+    Transforms exports back to assignments to `exports` and `module.exports`:
+
+    ```js
+    export const foo = 123;
+    export { bar as baz };
+    export default qux;
+    ```
+
+    to
+
+    ```js
+    exports.foo = 123;
+    exports.baz = bar;
+    exports.default = qux;
+    ```
+ */
+pub fn transform_export_to_assignment(report: SharedReport) -> impl Fold {
+    as_folder(TransformExportToAssignmentVisitor::new(report))
+}
+
+/**
+    The reverse of `cjs_to_esm()`, for publishing CommonJS builds of converted code.
+    The output is written the way `cjs_to_esm()` reads it, so converting it back gives the same imports and exports
+    again, apart from default imports, which come back as namespace imports.
+ */
+pub fn esm_to_cjs(report: SharedReport) -> impl Fold {
     chain!(
-        transform_imports(),
-        transform_exports(),
+        transform_import_to_require(report.clone()),
+        transform_export_to_assignment(report),
     )
-}
\ No newline at end of file
+}
@@ -1,26 +1,48 @@
+use std::collections::{HashMap, HashSet};
+
 use swc_core::{
-    // common::{DUMMY_SP, util::take::Take}, 
-    ecma::ast::*
+    common::{BytePos, DUMMY_SP, Span, comments::{Comment, Comments}},
+    ecma::{
+        ast::*,
+        atoms::JsWord,
+        visit::{Visit, VisitWith},
+    },
 };
 
 /**
- * Determines if a given string uses single quotes
+    Makes a string literal for `value`, quoted with `quote` (either `'` or `"`).
+    Backslashes, line breaks and the quote itself are escaped.
  */
-// pub fn is_single_quoted(s: &str) -> bool {
-//     s.starts_with('\'') && s.ends_with('\'')
-// }
+pub fn quote_str(span: Span, value: &str, quote: char) -> Str {
+    let mut raw = String::with_capacity(value.len() + 2);
+    raw.push(quote);
+    for c in value.chars() {
+        match c {
+            '\\' => raw.push_str("\\\\"),
+            '\n' => raw.push_str("\\n"),
+            '\r' => raw.push_str("\\r"),
+            '\u{2028}' => raw.push_str("\\u2028"),
+            '\u{2029}' => raw.push_str("\\u2029"),
+            c if c == quote => {
+                raw.push('\\');
+                raw.push(c);
+            },
+            c => raw.push(c),
+        }
+    }
+    raw.push(quote);
+    Str { span, value: value.into(), raw: Some(raw.into()) }
+}
 
-// /**
-//  * Replaces the quotes of a string with the opposite quotes style.
-//  * Warning: this does not account for escaped quotes.
-//  */
-// pub fn replace_quotes(s: &str) -> String {
-//     if is_single_quoted(s) {
-//         s.replace("'", "\"")
-//     } else {
-//         s.replace("\"", "'")
-//     }
-// }
+/**
+    The quote a string literal was written with, `'` unless it was written with `"`.
+ */
+pub fn quote_of(str: &Str) -> char {
+    match str.raw.as_ref().and_then(|raw| raw.chars().next()) {
+        Some('"') => '"',
+        _ => '\'',
+    }
+}
 
 /**
     Checks if a given string can be used as an identifier
@@ -37,7 +59,43 @@ pub fn is_valid_identifier(s: &str) -> bool {
     if s.contains(|c: char| !c.is_alphanumeric() && c != '_' && c != '$') {
         return false;
     }
-    return true;
+    true
+}
+
+/**
+    Returns the name a destructured property can be imported by, if it can be imported by name at all.
+ */
+pub fn import_name_from_prop_name(key: &PropName) -> Option<String> {
+    match key {
+        PropName::Ident(Ident { sym, .. }) => Some(sym.to_string()),
+        PropName::Str(Str { value, .. }) if is_valid_identifier(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/**
+    Derives a readable identifier from a module specifier, ie `./utils/foo-bar.js` becomes `fooBar`.
+    Falls back to `mod` if the specifier has nothing usable in it.
+ */
+pub fn module_name_from_src(src: &str) -> String {
+    let base = src.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+    let base = base.split('.').find(|part| !part.is_empty()).unwrap_or_default();
+    let mut name = String::new();
+    for (i, part) in base
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+        .filter(|part| !part.is_empty())
+        .enumerate()
+    {
+        let mut chars = part.chars();
+        if i > 0 {
+            name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        }
+        name.push_str(chars.as_str());
+    }
+    if name.is_empty() || !is_valid_identifier(&name) {
+        return "mod".into();
+    }
+    name
 }
 
 /**
@@ -51,7 +109,7 @@ pub fn if_export_default<T, F:FnOnce() -> T>(node: &AssignExpr, f: F) -> Option<
             if let Expr::Member(MemberExpr { obj, prop, .. }) = &**expr {
                 if let Expr::Ident(Ident { sym, .. }) = &**obj {
                     if sym == "module" {
-                        if let MemberProp::Ident(Ident { sym, .. }) = &*prop {
+                        if let MemberProp::Ident(Ident { sym, .. }) = prop {
                             if sym == "exports" {
                                 Some(f())
                             } else {
@@ -87,13 +145,10 @@ pub fn if_require_call_expr<T, F:FnOnce(&CallExpr, Str) -> T>(expr: &Expr, f: F)
         if let Callee::Expr(callee_expr) = &call_expr.callee {
             if let Expr::Ident(Ident { sym, .. }) = &**callee_expr {
                 if sym == "require" {
-                    if let Some(arg) = call_expr.args.get(0) {
-                        if let Expr::Lit(lit) = *arg.expr.to_owned() {
-                            let src = match lit {
-                                Lit::Str(s) => Some(s),
-                                _ => panic!("Unexpected require argument"),
-                            };
-                            Some(f(call_expr, src.unwrap()))
+                    if let Some(arg) = call_expr.args.first() {
+                        // Only string literals, `require(1)` is left alone like computed paths are
+                        if let Expr::Lit(Lit::Str(src)) = *arg.expr.to_owned() {
+                            Some(f(call_expr, src))
                         } else {
                             None
                         }
@@ -114,6 +169,819 @@ pub fn if_require_call_expr<T, F:FnOnce(&CallExpr, Str) -> T>(expr: &Expr, f: F)
     }
 }
 
+/**
+    Whether the expression is a free reference to `name`, one that isn't in `bindings`, see `collect_binding_ids()`.
+ */
+pub fn is_free_ident(expr: &Expr, name: &str, bindings: &HashSet<Id>) -> bool {
+    matches!(expr, Expr::Ident(ident) if &*ident.sym == name && !bindings.contains(&ident.to_id()))
+}
+
+/**
+    Whether the expression is `obj.prop` with `obj` a free reference, ie `is_free_member_of(expr, "require", "resolve",
+    bindings)` for `require.resolve`, see `is_free_ident()`.
+ */
+pub fn is_free_member_of(expr: &Expr, obj: &str, prop: &str, bindings: &HashSet<Id>) -> bool {
+    match expr {
+        Expr::Member(MemberExpr { obj: object, prop: MemberProp::Ident(property), .. }) => {
+            is_free_ident(object, obj, bindings) && &*property.sym == prop
+        },
+        _ => false,
+    }
+}
+
+/**
+    Whether the expression has a `require('foo')` call somewhere in it.
+ */
+pub fn has_require_call(expr: &Expr) -> bool {
+    let mut finder = RequireCallFinder::default();
+    expr.visit_with(&mut finder);
+    finder.found
+}
+
+#[derive(Default)]
+struct RequireCallFinder {
+    found: bool,
+}
+
+impl Visit for RequireCallFinder {
+    fn visit_expr(&mut self, e: &Expr) {
+        if if_require_call_expr(e, |_, _| ()).is_some() {
+            self.found = true;
+        } else {
+            e.visit_children_with(self);
+        }
+    }
+}
+
+/**
+    Whether evaluating the expression can't have side effects that another module could observe,
+    so it makes no difference whether a hoisted import runs before or after it.
+
+    Calls and assignments aren't pure, apart from `require('foo')` which is hoisted in the same order as the other
+    requires. Reading a property is assumed not to run a getter.
+ */
+pub fn is_pure_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(..) | Expr::Ident(..) | Expr::This(..) | Expr::Fn(..) | Expr::Arrow(..) => true,
+        Expr::Call(..) => if_require_call_expr(expr, |_, _| ()).is_some(),
+        Expr::Tpl(Tpl { exprs, .. }) => exprs.iter().all(|e| is_pure_expr(e)),
+        Expr::Array(ArrayLit { elems, .. }) => elems
+            .iter()
+            .flatten()
+            .all(|elem| elem.spread.is_none() && is_pure_expr(&elem.expr)),
+        Expr::Object(ObjectLit { props, .. }) => props.iter().all(|prop| match prop {
+            PropOrSpread::Spread(SpreadElement { expr, .. }) => is_pure_expr(expr),
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::Shorthand(..) => true,
+                Prop::KeyValue(KeyValueProp { key, value }) => is_pure_prop_name(key) && is_pure_expr(value),
+                Prop::Method(MethodProp { key, .. })
+                | Prop::Getter(GetterProp { key, .. })
+                | Prop::Setter(SetterProp { key, .. }) => is_pure_prop_name(key),
+                Prop::Assign(..) => false,
+            },
+        }),
+        Expr::Unary(UnaryExpr { op, arg, .. }) => *op != UnaryOp::Delete && is_pure_expr(arg),
+        Expr::Bin(BinExpr { left, right, .. }) => is_pure_expr(left) && is_pure_expr(right),
+        Expr::Cond(CondExpr { test, cons, alt, .. }) => is_pure_expr(test) && is_pure_expr(cons) && is_pure_expr(alt),
+        Expr::Member(MemberExpr { obj, prop, .. }) => {
+            is_pure_expr(obj) && !matches!(prop, MemberProp::Computed(ComputedPropName { expr, .. }) if !is_pure_expr(expr))
+        },
+        Expr::Paren(ParenExpr { expr, .. })
+        | Expr::TsAs(TsAsExpr { expr, .. })
+        | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+        | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+        | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+        | Expr::TsSatisfaction(TsSatisfactionExpr { expr, .. }) => is_pure_expr(expr),
+        Expr::Seq(SeqExpr { exprs, .. }) => exprs.iter().all(|e| is_pure_expr(e)),
+        _ => false,
+    }
+}
+
+fn is_pure_prop_name(key: &PropName) -> bool {
+    match key {
+        PropName::Computed(ComputedPropName { expr, .. }) => is_pure_expr(expr),
+        _ => true,
+    }
+}
+
+/**
+    Whether running a top-level statement can't have side effects, see `is_pure_expr()`.
+    Imports count as pure since they are hoisted in order with the requires, and so do function declarations.
+    Statements other than declarations and expressions, like `if` or loops, are assumed to have side effects.
+ */
+pub fn is_pure_module_item(item: &ModuleItem) -> bool {
+    match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => is_pure_decl(decl),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(ExportDefaultExpr { expr, .. })) => is_pure_expr(expr),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { decl, .. })) => match decl {
+            DefaultDecl::Class(ClassExpr { class, .. }) => is_pure_class(class),
+            _ => true,
+        },
+        ModuleItem::ModuleDecl(..) => true,
+        ModuleItem::Stmt(Stmt::Decl(decl)) => is_pure_decl(decl),
+        ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) => is_pure_expr(expr),
+        ModuleItem::Stmt(Stmt::Empty(..)) => true,
+        ModuleItem::Stmt(..) => false,
+    }
+}
+
+fn is_pure_decl(decl: &Decl) -> bool {
+    match decl {
+        Decl::Var(var) => var.decls.iter().all(is_pure_declarator),
+        Decl::Class(ClassDecl { class, .. }) => is_pure_class(class),
+        // Functions, and TypeScript declarations like enums that only set up an object
+        _ => true,
+    }
+}
+
+// Only what runs when the class is declared, its superclass, computed keys and static members
+fn is_pure_class(class: &Class) -> bool {
+    class.super_class.as_deref().is_none_or(is_pure_expr)
+        && class.body.iter().all(|member| match member {
+            ClassMember::Method(ClassMethod { key, .. }) => is_pure_prop_name(key),
+            ClassMember::ClassProp(ClassProp { key, value, is_static, .. }) => {
+                is_pure_prop_name(key) && (!is_static || value.as_deref().is_none_or(is_pure_expr))
+            },
+            ClassMember::PrivateProp(PrivateProp { value, is_static, .. }) => {
+                !is_static || value.as_deref().is_none_or(is_pure_expr)
+            },
+            ClassMember::StaticBlock(..) => false,
+            _ => true,
+        })
+}
+
+/**
+    Whether a declarator is pure, see `is_pure_expr()`, default values in its pattern included.
+ */
+pub fn is_pure_declarator(decl: &VarDeclarator) -> bool {
+    decl.init.as_deref().is_none_or(is_pure_expr) && is_pure_pat(&decl.name)
+}
+
+fn is_pure_pat(pat: &Pat) -> bool {
+    match pat {
+        Pat::Array(ArrayPat { elems, .. }) => elems.iter().flatten().all(is_pure_pat),
+        Pat::Object(ObjectPat { props, .. }) => props.iter().all(|prop| match prop {
+            ObjectPatProp::KeyValue(KeyValuePatProp { key, value }) => is_pure_prop_name(key) && is_pure_pat(value),
+            ObjectPatProp::Assign(AssignPatProp { value, .. }) => value.as_deref().is_none_or(is_pure_expr),
+            ObjectPatProp::Rest(RestPat { arg, .. }) => is_pure_pat(arg),
+        }),
+        Pat::Rest(RestPat { arg, .. }) => is_pure_pat(arg),
+        Pat::Assign(AssignPat { left, right, .. }) => is_pure_pat(left) && is_pure_expr(right),
+        Pat::Ident(..) | Pat::Invalid(..) | Pat::Expr(..) => true,
+    }
+}
+
+/**
+    Builds a `require('foo')` call, the reverse of `if_require_call_expr()`.
+ */
+pub fn require_call(src: Str) -> Expr {
+    Expr::Call(CallExpr {
+        span: src.span,
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("require".into(), src.span)))),
+        args: vec![Expr::Lit(Lit::Str(src)).into()],
+        type_args: None,
+    })
+}
+
+/**
+    Builds `import.meta.url`, or any other property of `import.meta`.
+ */
+pub fn import_meta(span: Span, prop: &str) -> Expr {
+    Expr::Member(MemberExpr {
+        span,
+        obj: Box::new(Expr::MetaProp(MetaPropExpr { span, kind: MetaPropKind::ImportMeta })),
+        prop: MemberProp::Ident(Ident::new(prop.into(), span)),
+    })
+}
+
+/**
+    Builds `module.exports`.
+ */
+pub fn module_exports(span: Span) -> Expr {
+    Expr::Member(MemberExpr {
+        span,
+        obj: Box::new(Expr::Ident(Ident::new("module".into(), span))),
+        prop: MemberProp::Ident(Ident::new("exports".into(), span)),
+    })
+}
+
+/**
+    Builds `exports.foo`, or `exports['foo-bar']` for names that aren't identifiers.
+ */
+pub fn exports_member(span: Span, name: &str) -> Expr {
+    let prop = if is_valid_identifier(name) {
+        MemberProp::Ident(Ident::new(name.into(), span))
+    } else {
+        MemberProp::Computed(ComputedPropName {
+            span,
+            expr: Box::new(Expr::Lit(Lit::Str(quote_str(span, name, '\'')))),
+        })
+    };
+    Expr::Member(MemberExpr {
+        span,
+        obj: Box::new(Expr::Ident(Ident::new("exports".into(), span))),
+        prop,
+    })
+}
+
+/**
+    The local name of `imported` in an `import { ... } from 'src'` of the module, if there is one.
+ */
+pub fn find_named_import(m: &Module, src: &str, imported: &str) -> Option<Ident> {
+    m.body.iter().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(import)) if !import.type_only && &*import.src.value == src => {
+            import.specifiers.iter().find_map(|specifier| match specifier {
+                ImportSpecifier::Named(ImportNamedSpecifier { local, imported: name, is_type_only: false, .. }) => {
+                    let name = match name {
+                        Some(ModuleExportName::Ident(name)) => &name.sym,
+                        Some(ModuleExportName::Str(name)) => &name.value,
+                        None => &local.sym,
+                    };
+                    (&**name == imported).then(|| local.clone())
+                },
+                _ => None,
+            })
+        },
+        _ => None,
+    })
+}
+
+/**
+    Adds `import { imported as local } from 'src'` to the module, to an `import { ... } from 'src'` that is already
+    there when possible. Nothing is added when `local` is imported already, see `find_named_import()`.
+ */
+pub fn add_named_import(m: &mut Module, src: &str, imported: &str, local: Ident) {
+    if find_named_import(m, src, imported).is_some_and(|existing| existing.to_id() == local.to_id()) {
+        return;
+    }
+    let specifier = ImportSpecifier::Named(ImportNamedSpecifier {
+        span: DUMMY_SP,
+        imported: (&*local.sym != imported).then(|| ModuleExportName::Ident(Ident::new(imported.into(), DUMMY_SP))),
+        local,
+        is_type_only: false,
+    });
+    let existing = m.body.iter_mut().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+            if !import.type_only
+                && &*import.src.value == src
+                && import.specifiers.iter().all(|s| matches!(s, ImportSpecifier::Named(..))) =>
+        {
+            Some(import)
+        },
+        _ => None,
+    });
+    match existing {
+        Some(import) => import.specifiers.push(specifier),
+        None => m.body.insert(
+            0,
+            ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                span: DUMMY_SP,
+                specifiers: vec![specifier],
+                src: Box::new(quote_str(DUMMY_SP, src, '\'')),
+                type_only: false,
+                asserts: None,
+            })),
+        ),
+    }
+}
+
+/**
+    Moves the comments of a replaced node over to the node taking its place.
+    Leading comments are keyed by the start of a node and trailing ones by its end.
+ */
+pub fn move_comments<C: Comments>(comments: &C, from: Span, to: Span) {
+    if to.is_dummy() {
+        return;
+    }
+    if from.lo != to.lo {
+        comments.move_leading(from.lo, to.lo);
+    }
+    if from.hi != to.hi {
+        comments.move_trailing(from.hi, to.hi);
+    }
+}
+
+/**
+    Whether the comment starts with `pragma`, ie `// cjs-to-esm-ignore-next-line native addon`.
+ */
+pub fn is_pragma(comment: &Comment, pragma: &str) -> bool {
+    comment.text.split_whitespace().next() == Some(pragma)
+}
+
+/**
+    Whether one of the comments leading `pos` starts with `pragma`.
+ */
+pub fn has_pragma<C: Comments>(comments: &C, pos: BytePos, pragma: &str) -> bool {
+    comments.with_leading(pos, |comments| comments.iter().any(|comment| is_pragma(comment, pragma)))
+}
+
+/**
+    Hands out generated identifiers like `_foo$1` that don't clash with any name already used in the module.
+ */
+#[derive(Default)]
+pub struct NameGenerator {
+    used: HashSet<JsWord>,
+    cnt: usize,
+}
+
+impl NameGenerator {
+    /**
+        Marks every identifier in the module as taken.
+        Should be called before the visitor starts making changes.
+     */
+    pub fn reserve_module(&mut self, m: &Module) {
+        let mut collector = IdentCollector::default();
+        m.visit_with(&mut collector);
+        self.used.extend(collector.idents);
+    }
+
+    pub fn ident(&mut self, name: &str) -> Ident {
+        loop {
+            self.cnt += 1;
+            let sym: JsWord = format!("_{}${}", name, self.cnt).into();
+            if self.used.insert(sym.clone()) {
+                return Ident::new(sym, DUMMY_SP);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct IdentCollector {
+    idents: HashSet<JsWord>,
+}
+
+impl Visit for IdentCollector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.idents.insert(ident.sym.clone());
+    }
+}
+
+/**
+    Collects the names that are used as values anywhere in the module, as opposed to only in type annotations.
+ */
+pub fn collect_value_references(m: &Module) -> HashSet<JsWord> {
+    let mut collector = ValueReferenceCollector::default();
+    m.visit_with(&mut collector);
+    collector.names
+}
+
+#[derive(Default)]
+struct ValueReferenceCollector {
+    names: HashSet<JsWord>,
+}
+
+impl Visit for ValueReferenceCollector {
+    fn visit_expr(&mut self, e: &Expr) {
+        e.visit_children_with(self);
+        if let Expr::Ident(id) = e {
+            self.names.insert(id.sym.clone());
+        }
+    }
+
+    fn visit_prop(&mut self, p: &Prop) {
+        p.visit_children_with(self);
+        if let Prop::Shorthand(id) = p {
+            self.names.insert(id.sym.clone());
+        }
+    }
+
+    fn visit_export_named_specifier(&mut self, s: &ExportNamedSpecifier) {
+        if let ModuleExportName::Ident(id) = &s.orig {
+            self.names.insert(id.sym.clone());
+        }
+    }
+}
+
+/**
+    Whether the module has free references to `require`, `module` or `exports`, see `collect_binding_ids()`, or uses
+    the TypeScript forms of `require` and `module.exports`. Modules without any are ES modules already.
+ */
+pub fn has_commonjs(m: &Module) -> bool {
+    let mut finder = CommonJsFinder { declared: collect_binding_ids(m), found: false };
+    m.visit_with(&mut finder);
+    finder.found
+}
+
+struct CommonJsFinder {
+    declared: HashSet<Id>,
+    found: bool,
+}
+
+impl Visit for CommonJsFinder {
+    fn visit_expr(&mut self, e: &Expr) {
+        match e {
+            Expr::Ident(ident) if matches!(&*ident.sym, "require" | "module" | "exports") => {
+                self.found |= !self.declared.contains(&ident.to_id());
+            },
+            _ => e.visit_children_with(self),
+        }
+    }
+
+    fn visit_ts_import_equals_decl(&mut self, d: &TsImportEqualsDecl) {
+        self.found |= matches!(d.module_ref, TsModuleRef::TsExternalModuleRef(..));
+    }
+
+    fn visit_ts_export_assignment(&mut self, _: &TsExportAssignment) {
+        self.found = true;
+    }
+}
+
+/**
+    Collects the names of all bindings declared anywhere in the module, imports included, ignoring scopes.
+ */
+pub fn collect_binding_names(m: &Module) -> HashSet<JsWord> {
+    collect_binding_ids(m).into_iter().map(|(sym, _)| sym).collect()
+}
+
+/**
+    Collects all bindings declared anywhere in the module by name and syntax context. Once `resolver` has run,
+    a reference has the context of the binding it refers to, so a reference whose id isn't in here is free,
+    ie the `require` of `require('path')` next to `(require) => require('./x')`.
+    Without `resolver` every context is the same and this is no different from `collect_binding_names()`.
+ */
+pub fn collect_binding_ids(m: &Module) -> HashSet<Id> {
+    let mut collector = BindingCollector::default();
+    m.visit_with(&mut collector);
+    collector.ids
+}
+
+#[derive(Default)]
+struct BindingCollector {
+    ids: HashSet<Id>,
+    in_assign_target: bool, // `foo = 1` parses `foo` as a binding too, which doesn't declare anything
+}
+
+impl Visit for BindingCollector {
+    fn visit_assign_expr(&mut self, e: &AssignExpr) {
+        self.in_assign_target = true;
+        e.left.visit_with(self);
+        self.in_assign_target = false;
+        e.right.visit_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, s: &ForOfStmt) {
+        self.in_assign_target = s.left.is_pat();
+        s.left.visit_with(self);
+        self.in_assign_target = false;
+        s.right.visit_with(self);
+        s.body.visit_with(self);
+    }
+
+    fn visit_for_in_stmt(&mut self, s: &ForInStmt) {
+        self.in_assign_target = s.left.is_pat();
+        s.left.visit_with(self);
+        self.in_assign_target = false;
+        s.right.visit_with(self);
+        s.body.visit_with(self);
+    }
+
+    fn visit_expr(&mut self, e: &Expr) {
+        let in_assign_target = std::mem::replace(&mut self.in_assign_target, false);
+        e.visit_children_with(self);
+        self.in_assign_target = in_assign_target;
+    }
+
+    fn visit_binding_ident(&mut self, id: &BindingIdent) {
+        if !self.in_assign_target {
+            self.ids.insert(id.id.to_id());
+        }
+    }
+
+    fn visit_fn_decl(&mut self, d: &FnDecl) {
+        d.visit_children_with(self);
+        self.ids.insert(d.ident.to_id());
+    }
+
+    fn visit_class_decl(&mut self, d: &ClassDecl) {
+        d.visit_children_with(self);
+        self.ids.insert(d.ident.to_id());
+    }
+
+    fn visit_assign_pat_prop(&mut self, p: &AssignPatProp) {
+        p.visit_children_with(self);
+        if !self.in_assign_target {
+            self.ids.insert(p.key.to_id());
+        }
+    }
+
+    fn visit_import_specifier(&mut self, s: &ImportSpecifier) {
+        let local = match s {
+            ImportSpecifier::Named(s) => &s.local,
+            ImportSpecifier::Default(s) => &s.local,
+            ImportSpecifier::Namespace(s) => &s.local,
+        };
+        self.ids.insert(local.to_id());
+    }
+}
+
+/**
+    Collects the names of the bindings in the module scope, the ones that can be exported.
+    That's imports, top-level declarations and `var`s outside of functions, which are hoisted up to the module.
+ */
+pub fn collect_top_level_names(m: &Module) -> HashSet<JsWord> {
+    let mut collector = TopLevelCollector::default();
+    for item in &m.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl { specifiers, .. })) => {
+                for specifier in specifiers {
+                    specifier.visit_with(&mut collector);
+                }
+            },
+            ModuleItem::Stmt(Stmt::Decl(decl)) | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                match decl {
+                    Decl::Var(var) => {
+                        for decl in &var.decls {
+                            collector.add_pat(&decl.name);
+                        }
+                        var.visit_children_with(&mut collector);
+                    },
+                    Decl::Fn(FnDecl { ident, .. }) | Decl::Class(ClassDecl { ident, .. }) => {
+                        collector.names.insert(ident.sym.clone());
+                    },
+                    _ => {}
+                }
+            },
+            _ => item.visit_with(&mut collector),
+        }
+    }
+    collector.names
+}
+
+#[derive(Default)]
+struct TopLevelCollector {
+    names: HashSet<JsWord>,
+}
+
+impl TopLevelCollector {
+    fn add_pat(&mut self, pat: &Pat) {
+        self.names.extend(pat_binding_idents(pat).into_iter().map(|id| id.sym));
+    }
+}
+
+impl Visit for TopLevelCollector {
+    fn visit_var_decl(&mut self, d: &VarDecl) {
+        if d.kind == VarDeclKind::Var {
+            d.decls.iter().for_each(|decl| self.add_pat(&decl.name));
+        }
+        d.visit_children_with(self);
+    }
+
+    fn visit_import_specifier(&mut self, s: &ImportSpecifier) {
+        let local = match s {
+            ImportSpecifier::Named(s) => &s.local,
+            ImportSpecifier::Default(s) => &s.local,
+            ImportSpecifier::Namespace(s) => &s.local,
+        };
+        self.names.insert(local.sym.clone());
+    }
+
+    // `var`s in functions belong to the function
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/**
+    The identifiers a pattern binds, in order, ie `foo` and `baz` for `{ foo, bar: [baz] }`.
+ */
+pub fn pat_binding_idents(pat: &Pat) -> Vec<Ident> {
+    let mut collector = PatIdentCollector::default();
+    pat.visit_with(&mut collector);
+    collector.idents
+}
+
+#[derive(Default)]
+struct PatIdentCollector {
+    idents: Vec<Ident>,
+}
+
+impl Visit for PatIdentCollector {
+    fn visit_binding_ident(&mut self, id: &BindingIdent) {
+        self.idents.push(id.id.clone());
+    }
+
+    fn visit_assign_pat_prop(&mut self, p: &AssignPatProp) {
+        self.idents.push(p.key.clone());
+    }
+
+    // Default values can hold functions with bindings of their own
+    fn visit_expr(&mut self, _: &Expr) {}
+}
+
+/**
+    Collects the names the module exports with `export` already, with the local binding they export when there is
+    one, ie `foo` for `export function foo() {}` and `export { foo as bar }` but not for `export { bar } from './bar'`.
+    `default` stands for the default export.
+ */
+pub fn collect_exported_names(m: &Module) -> HashMap<JsWord, Option<JsWord>> {
+    let mut exported = HashMap::new();
+    let name = |name: &ModuleExportName| match name {
+        ModuleExportName::Ident(ident) => ident.sym.clone(),
+        ModuleExportName::Str(str) => str.value.clone(),
+    };
+    for item in &m.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => match decl {
+                Decl::Var(var) => {
+                    for ident in var.decls.iter().flat_map(|decl| pat_binding_idents(&decl.name)) {
+                        exported.insert(ident.sym.clone(), Some(ident.sym));
+                    }
+                },
+                Decl::Fn(FnDecl { ident, .. }) | Decl::Class(ClassDecl { ident, .. }) => {
+                    exported.insert(ident.sym.clone(), Some(ident.sym.clone()));
+                },
+                _ => {},
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => {
+                for specifier in &export.specifiers {
+                    match specifier {
+                        ExportSpecifier::Named(ExportNamedSpecifier { orig, exported: as_name, .. }) => {
+                            let local = match orig {
+                                ModuleExportName::Ident(ident) if export.src.is_none() => Some(ident.sym.clone()),
+                                _ => None,
+                            };
+                            exported.insert(name(as_name.as_ref().unwrap_or(orig)), local);
+                        },
+                        ExportSpecifier::Namespace(ExportNamespaceSpecifier { name: as_name, .. }) => {
+                            exported.insert(name(as_name), None);
+                        },
+                        ExportSpecifier::Default(ExportDefaultSpecifier { exported: as_name }) => {
+                            exported.insert(as_name.sym.clone(), None);
+                        },
+                    }
+                }
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(..) | ModuleDecl::ExportDefaultExpr(..)) => {
+                exported.insert("default".into(), None);
+            },
+            _ => {},
+        }
+    }
+    exported
+}
+
+/**
+    Collects the export names that are assigned more than once anywhere in the module, ie `foo` for
+    `exports.foo = 1; if (bar) module.exports.foo = 2;`, counting the keys of objects assigned to `module.exports`.
+    None of these can be turned into a single `export`.
+ */
+pub fn collect_repeated_export_names(m: &Module) -> HashSet<JsWord> {
+    let mut collector = ExportAssignmentCollector::default();
+    m.visit_with(&mut collector);
+    collector.repeated
+}
+
+#[derive(Default)]
+struct ExportAssignmentCollector {
+    seen: HashSet<JsWord>,
+    repeated: HashSet<JsWord>,
+}
+
+impl ExportAssignmentCollector {
+    fn add(&mut self, name: &JsWord) {
+        if !self.seen.insert(name.clone()) {
+            self.repeated.insert(name.clone());
+        }
+    }
+}
+
+impl Visit for ExportAssignmentCollector {
+    fn visit_assign_expr(&mut self, e: &AssignExpr) {
+        e.visit_children_with(self);
+        if if_export_default(e, || ()).is_some() {
+            if let Expr::Object(ObjectLit { props, .. }) = &*e.right {
+                for prop in props {
+                    match prop {
+                        PropOrSpread::Prop(prop) => match &**prop {
+                            Prop::Shorthand(id) => self.add(&id.sym),
+                            Prop::KeyValue(KeyValueProp { key: PropName::Ident(id), .. }) => self.add(&id.sym),
+                            Prop::KeyValue(KeyValueProp { key: PropName::Str(str), .. }) => self.add(&str.value),
+                            _ => {}
+                        },
+                        PropOrSpread::Spread(..) => {}
+                    }
+                }
+            }
+            return;
+        }
+        if let PatOrExpr::Pat(pat) = &e.left {
+            if let Pat::Expr(expr) = &**pat {
+                if let Expr::Member(MemberExpr { obj, prop: MemberProp::Ident(prop), .. }) = &**expr {
+                    let is_exports = match &**obj {
+                        Expr::Ident(id) => id.sym == *"exports",
+                        Expr::Member(MemberExpr { obj, prop: MemberProp::Ident(exports), .. }) => {
+                            matches!(&**obj, Expr::Ident(module) if module.sym == *"module") && exports.sym == *"exports"
+                        },
+                        _ => false,
+                    };
+                    if is_exports {
+                        self.add(&prop.sym);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/**
+    Collects the names of all bindings that are assigned to after their declaration,
+    ie `foo = bar`, `foo++`, `for (foo of bar)` or `var foo` a second time.
+    Scopes are ignored so shadowed bindings with the same name are treated as reassigned too.
+ */
+pub fn collect_reassigned_names(m: &Module) -> HashSet<JsWord> {
+    let mut collector = ReassignmentCollector::default();
+    m.visit_with(&mut collector);
+    collector.names
+}
+
+#[derive(Default)]
+struct ReassignmentCollector {
+    names: HashSet<JsWord>,
+    vars: HashSet<JsWord>, // names declared with `var` so far
+}
+
+impl ReassignmentCollector {
+    fn add_pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(BindingIdent { id, .. }) => {
+                self.names.insert(id.sym.clone());
+            },
+            Pat::Expr(expr) => {
+                if let Expr::Ident(id) = &**expr {
+                    self.names.insert(id.sym.clone());
+                }
+            },
+            Pat::Array(ArrayPat { elems, .. }) => {
+                elems.iter().flatten().for_each(|elem| self.add_pat(elem));
+            },
+            Pat::Object(ObjectPat { props, .. }) => {
+                for prop in props {
+                    match prop {
+                        ObjectPatProp::KeyValue(KeyValuePatProp { value, .. }) => self.add_pat(value),
+                        ObjectPatProp::Assign(AssignPatProp { key, .. }) => {
+                            self.names.insert(key.sym.clone());
+                        },
+                        ObjectPatProp::Rest(RestPat { arg, .. }) => self.add_pat(arg),
+                    }
+                }
+            },
+            Pat::Rest(RestPat { arg, .. }) => self.add_pat(arg),
+            Pat::Assign(AssignPat { left, .. }) => self.add_pat(left),
+            Pat::Invalid(..) => {},
+        }
+    }
+}
+
+impl Visit for ReassignmentCollector {
+    fn visit_assign_expr(&mut self, e: &AssignExpr) {
+        e.visit_children_with(self);
+        match &e.left {
+            PatOrExpr::Pat(pat) => self.add_pat(pat),
+            PatOrExpr::Expr(expr) => {
+                if let Expr::Ident(id) = &**expr {
+                    self.names.insert(id.sym.clone());
+                }
+            },
+        }
+    }
+
+    fn visit_update_expr(&mut self, e: &UpdateExpr) {
+        e.visit_children_with(self);
+        if let Expr::Ident(id) = &*e.arg {
+            self.names.insert(id.sym.clone());
+        }
+    }
+
+    fn visit_for_in_stmt(&mut self, s: &ForInStmt) {
+        s.visit_children_with(self);
+        if let VarDeclOrPat::Pat(pat) = &s.left {
+            self.add_pat(pat);
+        }
+    }
+
+    fn visit_for_of_stmt(&mut self, s: &ForOfStmt) {
+        s.visit_children_with(self);
+        if let VarDeclOrPat::Pat(pat) = &s.left {
+            self.add_pat(pat);
+        }
+    }
+
+    // `var` can declare the same name again, which assigns it just the same
+    fn visit_var_decl(&mut self, d: &VarDecl) {
+        d.visit_children_with(self);
+        if d.kind == VarDeclKind::Var {
+            for ident in d.decls.iter().flat_map(|decl| pat_binding_idents(&decl.name)) {
+                if !self.vars.insert(ident.sym.clone()) {
+                    self.names.insert(ident.sym);
+                }
+            }
+        }
+    }
+}
+
 /**
     Macro for removing empty statements in a visitor class
  */
@@ -125,15 +993,11 @@ macro_rules! remove_empty {
 
             match s {
                 // Remove declarator statements without any declorations
-                Stmt::Decl(Decl::Var(var)) => {
-                    if var.decls.is_empty() {
-                        s.take();
-                    }
+                Stmt::Decl(Decl::Var(var)) if var.decls.is_empty() => {
+                    s.take();
                 }
-                Stmt::Expr(expr) => {
-                  if let Expr::Invalid(..) = *expr.expr {
+                Stmt::Expr(expr) if expr.expr.is_invalid() => {
                     s.take();
-                  }
                 },
                 _ => {}
             }
@@ -1,22 +1,261 @@
+use std::{collections::{HashMap, HashSet}, path::PathBuf};
+
 use swc_core::ecma::{
     ast::*,
-    visit::{VisitMut, VisitMutWith},
+    atoms::JsWord,
+    visit::{Fold, FoldWith, VisitMut, VisitMutWith},
+};
+use swc_core::common::{
+    DUMMY_SP, Span, Spanned, SyntaxContext,
+    comments::{Comment, CommentKind, Comments},
+    util::take::Take,
 };
-use swc_core::common::{DUMMY_SP, util::take::Take};
 
-use crate::{remove_empty, utils::{if_require_call_expr, if_export_default, is_valid_identifier}};
+use crate::{remove_empty, config::{Dirname, MainModule, QuotesStyle, RequireResolve, SideEffects}, project::{Interop, ProjectFile}, report::{ConversionReport, SharedReport}, resolve::{is_json, is_relative, rewrite_specifier}, utils::{
+    if_require_call_expr, if_export_default, import_name_from_prop_name, is_valid_identifier, module_name_from_src,
+    collect_reassigned_names, collect_binding_names, collect_binding_ids, has_require_call, is_pure_declarator, is_pure_module_item, collect_repeated_export_names, collect_exported_names, collect_top_level_names, collect_value_references, has_commonjs, has_pragma, is_pragma, exports_member, add_named_import, find_named_import, import_meta, is_free_ident, is_free_member_of, module_exports,
+    move_comments, pat_binding_idents, quote_of, quote_str, require_call, NameGenerator,
+}};
 
 pub struct NoopVisitor;
 
 impl VisitMut for NoopVisitor {}
 
+/**
+    Runs the wrapped transform while keeping the comments that lead the file (license headers, pragmas, etc.)
+    at the very top, even when the first statement is removed or imports are hoisted above it.
+    A `cjs-to-esm-ignore-next-line` comment is about the first statement instead, it stays with the statement
+    along with the comments after it.
+ */
+pub struct PreserveHeaderComments<C: Comments, F: Fold> {
+    pub comments: Option<C>,
+    pub inner: F,
+}
+
+impl<C: Comments, F: Fold> PreserveHeaderComments<C, F> {
+    pub fn new(comments: Option<C>, inner: F) -> Self {
+        Self { comments, inner }
+    }
+}
+
+impl<C: Comments, F: Fold> VisitMut for PreserveHeaderComments<C, F> {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        let header_pos = m.body.first().map(|item| item.span().lo);
+        let mut header = header_pos.and_then(|pos| self.comments.take_leading(pos));
+        if let (Some(pos), Some(header)) = (header_pos, &mut header) {
+            if let Some(i) = header.iter().position(|comment| is_pragma(comment, IGNORE_NEXT_LINE_PRAGMA)) {
+                self.comments.add_leading_comments(pos, header.split_off(i));
+            }
+        }
+
+        *m = m.take().fold_with(&mut self.inner);
+
+        if let (Some(header_pos), Some(mut header)) = (header_pos, header) {
+            let pos = m.body
+                .first()
+                .map(|item| item.span().lo)
+                .filter(|pos| !pos.is_dummy())
+                .unwrap_or(header_pos);
+            // The header goes before any comments the new first item already has
+            header.extend(self.comments.take_leading(pos).unwrap_or_default());
+            self.comments.add_leading_comments(pos, header);
+            // The comments of the start of the module are printed first, those left with the old first statement
+            // are printed with it once the module starts at the new first item
+            m.span.lo = pos;
+        }
+    }
+}
+
+/// Comment that leaves the whole file as it is, when it leads the file
+pub const DISABLE_PRAGMA: &str = "cjs-to-esm-disable";
+
+/// Comment that keeps the `require`, `module` and `exports` of the statement, property or expression right after it
+pub const IGNORE_NEXT_LINE_PRAGMA: &str = "cjs-to-esm-ignore-next-line";
+
+// What ignored references are renamed to while the wrapped transform runs, none of the visitors know them
+const IGNORED_REFERENCES: &[(&str, &str)] = &[
+    ("require", "require$cjs_to_esm_ignored"),
+    ("module", "module$cjs_to_esm_ignored"),
+    ("exports", "exports$cjs_to_esm_ignored"),
+];
+
+/**
+    Runs the wrapped transform over files that still have CommonJS in them. ES modules, and files led by a
+    `cjs-to-esm-disable` comment, are left as they are and marked as skipped in the report.
+
+    References to `require`, `module` and `exports` after a `cjs-to-esm-ignore-next-line` comment are hidden from
+    the wrapped transform, so the statement, property or expression they are in keeps them.
+ */
+pub struct SkipUnconvertedCode<C: Comments, F: Fold> {
+    pub comments: Option<C>,
+    pub report: SharedReport,
+    pub inner: F,
+}
+
+impl<C: Comments, F: Fold> SkipUnconvertedCode<C, F> {
+    pub fn new(comments: Option<C>, report: SharedReport, inner: F) -> Self {
+        Self { comments, report, inner }
+    }
+}
+
+impl<C: Comments, F: Fold> VisitMut for SkipUnconvertedCode<C, F> {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        let disabled = m.body.first().is_some_and(|item| has_pragma(&self.comments, item.span().lo, DISABLE_PRAGMA));
+        if disabled || !has_commonjs(m) {
+            self.report.borrow_mut().skipped = true;
+            return;
+        }
+
+        m.visit_mut_with(&mut HideIgnoredReferences { comments: &self.comments, ignoring: false });
+        *m = m.take().fold_with(&mut self.inner);
+        m.visit_mut_with(&mut RestoreIgnoredReferences);
+    }
+}
+
+struct HideIgnoredReferences<'a, C: Comments> {
+    comments: &'a Option<C>,
+    ignoring: bool,
+}
+
+impl<C: Comments> HideIgnoredReferences<'_, C> {
+    fn visit_mut_next_line<T: Spanned + VisitMutWith<Self>>(&mut self, node: &mut T) {
+        if !self.ignoring && has_pragma(self.comments, node.span().lo, IGNORE_NEXT_LINE_PRAGMA) {
+            self.ignoring = true;
+            node.visit_mut_children_with(self);
+            self.ignoring = false;
+        } else {
+            node.visit_mut_children_with(self);
+        }
+    }
+}
+
+impl<C: Comments> VisitMut for HideIgnoredReferences<'_, C> {
+    fn visit_mut_stmt(&mut self, s: &mut Stmt) {
+        self.visit_mut_next_line(s);
+    }
+
+    fn visit_mut_prop(&mut self, p: &mut Prop) {
+        self.visit_mut_next_line(p);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        self.visit_mut_next_line(e);
+        if let (true, Expr::Ident(ident)) = (self.ignoring, e) {
+            if let Some((_, hidden)) = IGNORED_REFERENCES.iter().find(|(name, _)| &*ident.sym == *name) {
+                ident.sym = (*hidden).into();
+            }
+        }
+    }
+}
+
+struct RestoreIgnoredReferences;
+
+impl VisitMut for RestoreIgnoredReferences {
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        if let Some((name, _)) = IGNORED_REFERENCES.iter().find(|(_, hidden)| &*ident.sym == *hidden) {
+            ident.sym = (*name).into();
+        }
+    }
+}
+
+/**
+    Turns the TypeScript flavour of CommonJS into the plain one so the other visitors can convert it,
+    ie `import foo = require('foo')` into `const foo = require('foo')` and `export = foo` into `module.exports = foo`.
+    Imports that are only ever used as types become `import type * as foo from 'foo'` right away.
+ */
+#[derive(Default)]
+pub struct TransformTsModuleSyntax {
+    pub values: HashSet<JsWord>, // names used as values, the other imports are only used as types
+    pub report: SharedReport,
+}
+
+impl TransformTsModuleSyntax {
+    pub fn new(report: SharedReport) -> Self {
+        Self { values: HashSet::new(), report }
+    }
+
+    fn transform_item(&mut self, item: ModuleItem) -> Vec<ModuleItem> {
+        match item {
+            // `import foo = Foo.Bar` aliases a namespace, only `import foo = require('foo')` is an import
+            ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(decl)) if decl.module_ref.is_ts_external_module_ref() => {
+                let TsImportEqualsDecl { span, is_export, is_type_only, id, module_ref, .. } = *decl;
+                let src = match module_ref {
+                    TsModuleRef::TsExternalModuleRef(TsExternalModuleRef { expr, .. }) => expr,
+                    TsModuleRef::TsEntityName(..) => unreachable!(),
+                };
+                if is_type_only || (!is_export && !self.values.contains(&id.sym)) {
+                    self.report.borrow_mut().imports.namespace += 1;
+                    return vec![ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                        span,
+                        specifiers: vec![ImportSpecifier::Namespace(ImportStarAsSpecifier { span: id.span, local: id.clone() })],
+                        src: Box::new(src),
+                        type_only: true,
+                        asserts: None,
+                    }))];
+                }
+                let mut items = vec![ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                    span,
+                    kind: VarDeclKind::Const,
+                    declare: false,
+                    decls: vec![VarDeclarator {
+                        span,
+                        name: Pat::Ident(id.clone().into()),
+                        init: Some(Box::new(require_call(src))),
+                        definite: false,
+                    }],
+                }))))];
+                if is_export {
+                    items.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                        span: DUMMY_SP,
+                        specifiers: vec![ExportSpecifier::Named(ExportNamedSpecifier {
+                            span: DUMMY_SP,
+                            orig: ModuleExportName::Ident(id),
+                            exported: None,
+                            is_type_only: false,
+                        })],
+                        src: None,
+                        type_only: false,
+                        asserts: None,
+                    })));
+                }
+                items
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::TsExportAssignment(TsExportAssignment { span, expr })) => {
+                vec![ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                    span,
+                    expr: Box::new(Expr::Assign(AssignExpr {
+                        span,
+                        op: AssignOp::Assign,
+                        left: PatOrExpr::Pat(Box::new(Pat::Expr(Box::new(module_exports(span))))),
+                        right: expr,
+                    })),
+                }))]
+            },
+            item => vec![item],
+        }
+    }
+}
+
+impl VisitMut for TransformTsModuleSyntax {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.values = collect_value_references(m);
+        m.body = m.body.take().into_iter().flat_map(|item| self.transform_item(item)).collect();
+    }
+}
+
+#[derive(Default)]
 pub struct TransformModuleDefaultExport {
-    pub export: Option<ExportDefaultExpr>
+    pub export: Option<ExportDefaultExpr>,
+    pub report: SharedReport,
+    pub bound: HashSet<JsWord>,
+    // The module has an `export default` already
+    pub has_default: bool,
+    statement: Span, // expression of the statement being visited, see `is_statement()`
 }
 
 impl TransformModuleDefaultExport {
-    pub fn new() -> Self {
-        Self { export: None }
+    pub fn new(report: SharedReport) -> Self {
+        Self { export: None, report, bound: HashSet::new(), has_default: false, statement: DUMMY_SP }
     }
 }
 
@@ -24,51 +263,106 @@ impl VisitMut for TransformModuleDefaultExport {
     remove_empty!();
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.bound = collect_top_level_names(m);
+        self.has_default = collect_exported_names(m).contains_key(&JsWord::from("default"));
         m.visit_mut_children_with(self);
 
         if let Some(export) = self.export.take() {
-            m.body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)));
+            let mut report = self.report.borrow_mut();
+            report.default_exports += 1;
+            report.warn(export.span, "`module.exports` was converted to a default export, imports of this file may need updating");
+            // `export default function() {}` is a declaration, printed as an expression it would leave a stray `;`
+            let decl = match *export.expr {
+                Expr::Fn(f) => ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span: export.span, decl: DefaultDecl::Fn(f) }),
+                Expr::Class(c) => {
+                    ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span: export.span, decl: DefaultDecl::Class(c) })
+                },
+                expr => ModuleDecl::ExportDefaultExpr(ExportDefaultExpr { span: export.span, expr: Box::new(expr) }),
+            };
+            m.body.push(ModuleItem::ModuleDecl(decl));
         }
     }
 
     fn visit_mut_expr_stmt(&mut self, e: &mut ExprStmt) {
+        let expr_span = e.expr.span();
+        self.statement = expr_span;
         e.visit_mut_children_with(self);
         // If left side is invalid then remove
-        if let Expr::Assign(a) = &*e.expr {
-            match &a.left {
-                PatOrExpr::Pat(pat) => {
-                    if let Pat::Invalid(..) = **pat {
-                        e.expr.take();
-                    }
+        if let Expr::Assign(AssignExpr { left: PatOrExpr::Pat(pat), .. }) = &*e.expr {
+            if let Pat::Invalid(..) = **pat {
+                // The export replaces the whole statement, so it takes over its span and with that its comments
+                if let Some(export) = self.export.as_mut().filter(|export| export.span == expr_span) {
+                    export.span = e.span;
                 }
-                _ => {}
+                e.expr.take();
             }
         }
     }
 
     fn visit_mut_assign_expr(&mut self, node: &mut AssignExpr) {
+        // Only assignments that make up a whole statement, `foo = module.exports = bar` is left alone
+        let is_statement = is_statement(self.statement, node);
         node.visit_mut_children_with(self);
+        if !is_statement {
+            return;
+        }
+        if self.has_default && if_export_default(node, || ()).is_some() {
+            self.report.borrow_mut().warn(
+                node.span,
+                "The file has an `export default` already, the assignment to `module.exports` was left as it is",
+            );
+            return;
+        }
 
         if_export_default(
             &node.to_owned(), 
             || {
-                // TODO: this is a fallback for when the default export is not a pure object
-                // so if you make it here then a warning should be shown.
-                let expr = node.right.take();
+                // This is a fallback for when the default export is not a pure object,
+                // the warning is added to the report once the export is in place.
+                let span = node.span;
+                let mut expr = node.right.take();
+                // `export default function foo() {}` declares `foo`, which must not clash with another `foo`
+                let name = match &*expr {
+                    Expr::Fn(FnExpr { ident: Some(ident), .. }) | Expr::Class(ClassExpr { ident: Some(ident), .. }) => {
+                        Some(&ident.sym)
+                    },
+                    _ => None,
+                };
+                if name.is_some_and(|name| self.bound.contains(name)) {
+                    expr = Box::new(Expr::Paren(ParenExpr { span: expr.span(), expr }));
+                }
                 node.take();
-                self.export = Some(ExportDefaultExpr { span: DUMMY_SP, expr });
+                self.export = Some(ExportDefaultExpr { span, expr });
             }
         );
     }
 }
 
+#[derive(Default)]
 pub struct TransformModuleExportsNamedExprVisitor {
-    pub exports: Vec<ExportDecl>,
+    pub exports: Vec<ModuleDecl>,
+    pub decls: Vec<VarDeclarator>,
+    pub names: NameGenerator,
+    // Names that are declared or referenced in the module, so `export const foo` would clash with them
+    pub taken: HashSet<JsWord>,
+    pub repeated: HashSet<JsWord>,
+    pub exported: HashMap<JsWord, Option<JsWord>>,
+    pub report: SharedReport,
+    statement: Span,
 }
 
 impl TransformModuleExportsNamedExprVisitor {
-    pub fn new() -> Self {
-        Self { exports: vec![] }
+    pub fn new(report: SharedReport) -> Self {
+        Self {
+            exports: vec![],
+            decls: vec![],
+            names: NameGenerator::default(),
+            taken: HashSet::new(),
+            repeated: HashSet::new(),
+            exported: HashMap::new(),
+            report,
+            statement: DUMMY_SP,
+        }
     }
 }
 
@@ -76,22 +370,32 @@ impl VisitMut for TransformModuleExportsNamedExprVisitor {
     remove_empty!();
 
     fn visit_mut_expr_stmt(&mut self, e: &mut ExprStmt) {
+        let expr_span = e.expr.span();
+        self.statement = expr_span;
         e.visit_mut_children_with(self);
         // If left side is invalid then remove
-        if let Expr::Assign(a) = &*e.expr {
-            match &a.left {
-                PatOrExpr::Pat(pat) => {
-                    if let Pat::Invalid(..) = **pat {
-                        e.expr.take();
+        if let Expr::Assign(AssignExpr { left: PatOrExpr::Pat(pat), .. }) = &*e.expr {
+            if let Pat::Invalid(..) = **pat {
+                // The export replaces the whole statement, so it takes over its span and with that its comments
+                for export in &mut self.exports {
+                    match export {
+                        ModuleDecl::ExportDecl(export) if export.span == expr_span => export.span = e.span,
+                        ModuleDecl::ExportNamed(export) if export.span == expr_span => export.span = e.span,
+                        _ => {}
                     }
                 }
-                _ => {}
+                e.expr.take();
             }
         }
     }
 
     fn visit_mut_assign_expr(&mut self, e: &mut AssignExpr) {
+        // Only assignments that make up a whole statement, `exports.foo = exports.bar = 1` is left alone
+        let is_statement = is_statement(self.statement, e);
         e.visit_mut_children_with(self);
+        if !is_statement {
+            return;
+        }
         if e.right.is_object() {
             return;
         }
@@ -100,16 +404,18 @@ impl VisitMut for TransformModuleExportsNamedExprVisitor {
             if let Pat::Expr(expr) = &**pat {
                 if let Expr::Member(mem_expr) = &**expr {
                     // Get the identifier from last member expression
-                    let ident = mem_expr
-                        .prop
-                        .as_ident()
-                        .unwrap();
+                    let ident = match &mem_expr.prop {
+                        MemberProp::Ident(ident) => ident,
+                        // `exports['foo-bar'] = 1` has no name to declare
+                        _ => return,
+                    };
+                    if self.repeated.contains(&ident.sym) {
+                        return;
+                    }
                     let mut is_match = false;
                     match &*mem_expr.obj {
-                        Expr::Ident(ident) => {
-                            if ident.sym == *"exports" {
-                                is_match = true;
-                            }
+                        Expr::Ident(ident) if ident.sym == *"exports" => {
+                            is_match = true;
                         },
                         Expr::Member(mem_expr_2) => {
                             if let Expr::Ident(obj) = &*mem_expr_2.obj {
@@ -125,21 +431,50 @@ impl VisitMut for TransformModuleExportsNamedExprVisitor {
                     if !is_match {
                         return;
                     }
+                    if self.exported.contains_key(&ident.sym) {
+                        self.report.borrow_mut().warn(e.span, already_exported(&ident.sym));
+                        return;
+                    }
+                    if self.taken.contains(&ident.sym) {
+                        // `exports.foo = foo + 1` can't become `export const foo = foo + 1`,
+                        // the value is declared under a generated name and exported as `foo` instead
+                        let local = self.names.ident(&ident.sym);
+                        self.decls.push(VarDeclarator {
+                            span: e.span,
+                            name: Pat::Ident(local.clone().into()),
+                            init: Some(e.right.clone()),
+                            definite: false,
+                        });
+                        self.exports.push(ModuleDecl::ExportNamed(NamedExport {
+                            span: e.span,
+                            src: None,
+                            specifiers: vec![ExportSpecifier::Named(ExportNamedSpecifier {
+                                span: e.span,
+                                orig: ModuleExportName::Ident(local),
+                                exported: Some(ModuleExportName::Ident(ident.to_owned())),
+                                is_type_only: false,
+                            })],
+                            type_only: false,
+                            asserts: None,
+                        }));
+                        e.take();
+                        return;
+                    }
                     // Add ExportDecl
-                    self.exports.push(ExportDecl {
-                        span: DUMMY_SP,
+                    self.exports.push(ModuleDecl::ExportDecl(ExportDecl {
+                        span: e.span,
                         decl: Decl::Var(Box::new(VarDecl {
-                            span: DUMMY_SP,
+                            span: e.span,
                             kind: VarDeclKind::Const,
                             declare: false,
                             decls: vec![VarDeclarator {
-                                span: DUMMY_SP,
+                                span: e.span,
                                 name: Pat::Ident(ident.to_owned().into()),
                                 init: Some(e.right.clone()),
                                 definite: false,
                             }],
                         })),
-                    });
+                    }));
                     e.take();
                 }
             }
@@ -147,24 +482,50 @@ impl VisitMut for TransformModuleExportsNamedExprVisitor {
     }
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.names.reserve_module(m);
+        self.taken = collect_binding_names(m);
+        self.taken.extend(collect_value_references(m));
+        self.repeated = collect_repeated_export_names(m);
+        self.exported = collect_exported_names(m);
         m.visit_mut_children_with(self);
-        for decl in &self.exports {
+        self.report.borrow_mut().exports.declarations += self.exports.len();
+        for decl in &self.decls {
             m.body.push(
-                ModuleItem::ModuleDecl(
-                    ModuleDecl::ExportDecl(decl.to_owned())
-                ),
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                    span: decl.span,
+                    kind: VarDeclKind::Const,
+                    decls: vec![decl.to_owned()],
+                    declare: false,
+                }))))
             );
         }
+        for decl in &self.exports {
+            m.body.push(ModuleItem::ModuleDecl(decl.to_owned()));
+        }
     }
 }
 
+#[derive(Default)]
 pub struct TransformModuleExportsIdentVisitor {
     pub exports: Vec<NamedExport>,
+    pub report: SharedReport,
+    // Only bindings of the module can be exported by name, globals are left for the named expression visitor
+    pub bound: HashSet<JsWord>,
+    pub repeated: HashSet<JsWord>,
+    pub exported: HashMap<JsWord, Option<JsWord>>,
+    statement: Span,
 }
 
 impl TransformModuleExportsIdentVisitor {
-    pub fn new() -> Self {
-        Self { exports: vec![] }
+    pub fn new(report: SharedReport) -> Self {
+        Self {
+            exports: vec![],
+            report,
+            bound: HashSet::new(),
+            repeated: HashSet::new(),
+            exported: HashMap::new(),
+            statement: DUMMY_SP,
+        }
     }
 }
 
@@ -172,29 +533,43 @@ impl VisitMut for TransformModuleExportsIdentVisitor {
     remove_empty!();
 
     fn visit_mut_expr_stmt(&mut self, e: &mut ExprStmt) {
+        let expr_span = e.expr.span();
+        self.statement = expr_span;
         e.visit_mut_children_with(self);
         // If left side is invalid then remove
-        if let Expr::Assign(a) = &*e.expr {
-            match &a.left {
-                PatOrExpr::Pat(pat) => {
-                    if let Pat::Invalid(..) = **pat {
-                        e.expr.take();
-                    }
+        if let Expr::Assign(AssignExpr { left: PatOrExpr::Pat(pat), .. }) = &*e.expr {
+            if let Pat::Invalid(..) = **pat {
+                // The export replaces the whole statement, so it takes over its span and with that its comments
+                if let Some(export) = self.exports.iter_mut().find(|export| export.span == expr_span) {
+                    export.span = e.span;
                 }
-                _ => {}
+                e.expr.take();
             }
         }
     }
 
     fn visit_mut_assign_expr(&mut self, e: &mut AssignExpr) {
+        // Only assignments that make up a whole statement, `exports.foo = exports.bar = 1` is left alone
+        let is_statement = is_statement(self.statement, e);
         e.visit_mut_children_with(self);
+        if !is_statement {
+            return;
+        }
         // check if module.exports.foo = bar or exports.foo = bar;
         if let Expr::Ident(rhs) = &*e.right {
+            if !self.bound.contains(&rhs.sym) {
+                return;
+            }
             if let PatOrExpr::Pat(pat) = &e.left {
                 if let Pat::Expr(expr) = &**pat {
                     if let Expr::Member(mem_expr) = &**expr {
                         // Get the identifier from last member expression
+                        let name = match &mem_expr.prop {
+                            MemberProp::Ident(m) => m.sym.clone(),
+                            _ => return,
+                        };
                         let exported = match &mem_expr.prop {
+                            MemberProp::Ident(m) if self.repeated.contains(&m.sym) => return,
                             MemberProp::Ident(m) => {
                                 // Check that rhs.sym is the same as m.sym
                                 if rhs.sym == m.sym {
@@ -203,14 +578,13 @@ impl VisitMut for TransformModuleExportsIdentVisitor {
                                     Some(ModuleExportName::Ident(m.clone()))
                                 }
                             }
-                            _ => None
+                            // `exports['foo'] = bar` is left alone like the other computed names
+                            _ => return,
                         };
                         let mut is_match = false;
                         match &*mem_expr.obj {
-                            Expr::Ident(ident) => {
-                                if ident.sym == *"exports" {
-                                    is_match = true;
-                                }
+                            Expr::Ident(ident) if ident.sym == *"exports" => {
+                                is_match = true;
                             },
                             Expr::Member(mem_expr_2) => {
                                 if let Expr::Ident(obj) = &*mem_expr_2.obj {
@@ -226,16 +600,25 @@ impl VisitMut for TransformModuleExportsIdentVisitor {
                         if !is_match {
                             return;
                         }
+                        match self.exported.get(&name) {
+                            // `export function foo() {}` along with `exports.foo = foo`, the assignment has no use
+                            Some(Some(local)) if *local == rhs.sym => {
+                                e.take();
+                                return;
+                            },
+                            Some(_) => {
+                                self.report.borrow_mut().warn(e.span, already_exported(&name));
+                                return;
+                            },
+                            None => {},
+                        }
                         self.exports.push(NamedExport {
-                            span: DUMMY_SP,
+                            span: e.span,
                             src: None,
                             specifiers: vec![
                                 ExportSpecifier::Named(ExportNamedSpecifier {
-                                    span: DUMMY_SP,
-                                    orig: ModuleExportName::Ident(Ident::new(
-                                        rhs.sym.to_owned(),
-                                        DUMMY_SP,
-                                    )),
+                                    span: e.span,
+                                    orig: ModuleExportName::Ident(rhs.to_owned()),
                                     exported,
                                     is_type_only: false,
                                 }),
@@ -251,7 +634,11 @@ impl VisitMut for TransformModuleExportsIdentVisitor {
     }
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.bound = collect_top_level_names(m);
+        self.repeated = collect_repeated_export_names(m);
+        self.exported = collect_exported_names(m);
         m.visit_mut_children_with(self);
+        self.report.borrow_mut().exports.named += self.exports.len();
         for decl in &self.exports {
             m.body.push(
                 ModuleItem::ModuleDecl(
@@ -262,53 +649,177 @@ impl VisitMut for TransformModuleExportsIdentVisitor {
     }
 }
 
-pub struct TransformRequireIdentVisitor {
+/**
+    Splits declarations of several declarators with a `require` among them into one statement per declarator,
+    so each require is converted on its own.
+
+    The imports end up hoisted above everything else, so a declarator is only split off while the ones before it are
+    pure. From the first impure declarator that is followed by a require on, the declarators stay together in one
+    statement, which the other visitors leave alone, and a warning says why the require is still there.
+ */
+pub struct SplitRequireDeclarations {
+    pub strategy: SideEffects,
+    pub report: SharedReport,
+}
+
+impl SplitRequireDeclarations {
+    pub fn new(strategy: SideEffects, report: SharedReport) -> Self {
+        Self { strategy, report }
+    }
+
+    fn split(&mut self, var: VarDecl) -> Vec<VarDecl> {
+        let has_require = |decl: &VarDeclarator| decl.init.as_deref().is_some_and(has_require_call);
+        if var.decls.len() < 2 || !var.decls.iter().any(has_require) {
+            return vec![var];
+        }
+        let VarDecl { span, kind, declare, mut decls } = var;
+        let kept_from = decls
+            .iter()
+            .position(|decl| !is_pure_declarator(decl))
+            .filter(|&impure| decls[impure + 1..].iter().any(has_require))
+            .unwrap_or(decls.len());
+        let kept = decls.split_off(kept_from);
+
+        let mut vars: Vec<VarDecl> = decls
+            .into_iter()
+            .map(|decl| VarDecl { span: decl.span, kind, declare, decls: vec![decl] })
+            .collect();
+        if let Some(require) = kept.iter().skip(1).find(|decl| has_require(decl)) {
+            // With `SideEffects::CreateRequire` it is made with `createRequire()` by `CheckSideEffectOrder`
+            if self.strategy == SideEffects::Warn {
+                self.report.borrow_mut().warn(
+                    require.span,
+                    "`require` was left as it is, the declarators before it have side effects that would run after the import",
+                );
+            }
+            let kept_span = kept[0].span.with_hi(kept[kept.len() - 1].span.hi);
+            vars.push(VarDecl { span: kept_span, kind, declare, decls: kept });
+        }
+        // The statements take over the start and end of the declaration, where its comments are
+        if let Some(first) = vars.first_mut() {
+            first.span = first.span.with_lo(span.lo);
+        }
+        if let Some(last) = vars.last_mut() {
+            last.span = last.span.with_hi(span.hi);
+        }
+        vars
+    }
+}
+
+impl VisitMut for SplitRequireDeclarations {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.visit_mut_children_with(self);
+        for item in items.take() {
+            match item {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => {
+                    let vars = self.split(*var).into_iter().map(|var| ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(var)))));
+                    items.extend(vars);
+                },
+                item => items.push(item),
+            }
+        }
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+        for stmt in stmts.take() {
+            match stmt {
+                Stmt::Decl(Decl::Var(var)) => {
+                    stmts.extend(self.split(*var).into_iter().map(|var| Stmt::Decl(Decl::Var(Box::new(var)))));
+                },
+                stmt => stmts.push(stmt),
+            }
+        }
+    }
+}
+
+pub struct TransformRequireIdentVisitor<C: Comments> {
+    pub comments: Option<C>,
+    pub report: SharedReport,
+    pub project: Option<ProjectFile>,
     pub imports: Vec<ModuleDecl>,
+    pub reassigned: HashSet<JsWord>,
+    pub names: NameGenerator, // used to name imports of reassigned bindings
 }
 
-impl TransformRequireIdentVisitor {
-    pub fn new() -> Self {
+impl<C: Comments> TransformRequireIdentVisitor<C> {
+    pub fn new(comments: Option<C>, report: SharedReport, project: Option<ProjectFile>) -> Self {
         Self {
+            comments,
+            report,
+            project,
             imports: vec![],
+            reassigned: HashSet::new(),
+            names: NameGenerator::default(),
         }
     }
 }
 
-impl VisitMut for TransformRequireIdentVisitor {
+impl<C: Comments> VisitMut for TransformRequireIdentVisitor<C> {
     remove_empty!();
 
     // Kinda messy. Could use a refactor?
     fn visit_mut_var_decl(&mut self, d: &mut VarDecl) {
         d.visit_mut_children_with(self);
+        // Kept together by `SplitRequireDeclarations` because of side effects before the require
+        if d.decls.len() > 1 {
+            return;
+        }
+        let kind = d.kind;
+        let mut import_span: Option<Span> = None;
         // Remove any declarations that match the pattern `const foo = require('foo')`
         d.decls.retain_mut(|decl| {
-            if let Pat::Ident(name) = &decl.name {
+            // `let foo;` and `for (const foo of bar)` have nothing to convert
+            if let (Pat::Ident(name), Some(init)) = (&decl.name, &decl.init) {
+                // Imports are immutable so a binding that gets reassigned later on is imported
+                // under a generated name and copied into the original `let`/`var`.
+                let is_mutable = kind != VarDeclKind::Const && self.reassigned.contains(&name.sym);
+                let local = if is_mutable {
+                    self.names.ident(&name.sym)
+                } else {
+                    name.id.to_owned()
+                };
                 if_require_call_expr(
-                    &decl.init.as_ref().unwrap(),
-                    |_expr, src| {
+                    init,
+                    |expr, src| {
+                        import_span.get_or_insert(expr.span);
+                        // What `require` returned is the default export for CommonJS packages and files that ended up with one,
+                        // and React is always imported that way
+                        let specifier = if is_default_import(&self.project, &src) {
+                            self.report.borrow_mut().imports.default += 1;
+                            ImportSpecifier::Default(ImportDefaultSpecifier { span: decl.span, local: local.clone() })
+                        } else {
+                            self.report.borrow_mut().imports.namespace += 1;
+                            ImportSpecifier::Namespace(ImportStarAsSpecifier { span: decl.span, local: local.clone() })
+                        };
                         let import = ModuleDecl::Import(ImportDecl {
-                            span: DUMMY_SP,
-                            specifiers: vec![ImportSpecifier::Namespace(
-                                ImportStarAsSpecifier { 
-                                    span: DUMMY_SP, 
-                                    local: Ident::new(name.sym.to_owned(), DUMMY_SP)
-                                }
-                            )],
+                            span: expr.span,
+                            specifiers: vec![specifier],
                             src: Box::new(src.to_owned()),
                             type_only: false,
                             asserts: None,
                         });
                         self.imports.push(import);
-                        false
+                        is_mutable
+                    }
+                ).inspect(|&keep| {
+                    if keep {
+                        decl.init = Some(Box::new(Expr::Ident(local)));
                     }
-                ).unwrap_or(true)
+                }).unwrap_or(true)
             } else {
                 true
             }
         });
+        // The whole statement was converted so its comments go to the first import
+        if let (true, Some(import_span)) = (d.decls.is_empty(), import_span) {
+            move_comments(&self.comments, d.span, import_span);
+        }
     }
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.reassigned = collect_reassigned_names(m);
+        self.names.reserve_module(m);
         m.visit_mut_children_with(self);
         for decl in &self.imports {
             m.body.insert(
@@ -320,32 +831,37 @@ impl VisitMut for TransformRequireIdentVisitor {
     }
 }
 
-pub struct TransformRequireStatementVistor {
+pub struct TransformRequireStatementVistor<C: Comments> {
+    pub comments: Option<C>,
+    pub report: SharedReport,
     // maintian a list of raw require statements
-    pub imports: Vec<Str>
+    pub imports: Vec<(Str, Span)>
 }
 
-impl TransformRequireStatementVistor {
-    pub fn new() -> Self {
+impl<C: Comments> TransformRequireStatementVistor<C> {
+    pub fn new(comments: Option<C>, report: SharedReport) -> Self {
         Self {
+            comments,
+            report,
             imports: vec![],
         }
     }
 }
 
-impl VisitMut for TransformRequireStatementVistor {
+impl<C: Comments> VisitMut for TransformRequireStatementVistor<C> {
     remove_empty!();
     
     fn visit_mut_module(&mut self, m: &mut Module) {
         m.visit_mut_children_with(self);
+        self.report.borrow_mut().imports.side_effect += self.imports.len();
         // Add `import 'test';`
-        for str in &self.imports {
+        for (str, span) in &self.imports {
             m.body.insert(
                 0,
                 ModuleItem::ModuleDecl(
                     ModuleDecl::Import(
                         ImportDecl {
-                            span: DUMMY_SP,
+                            span: *span,
                             specifiers: vec![],
                             src: Box::new(str.to_owned()),
                             type_only: false,
@@ -362,9 +878,10 @@ impl VisitMut for TransformRequireStatementVistor {
         // print!("{:?}", s);
         if_require_call_expr(
             &s.expr.to_owned(), 
-            |_expr, src| {
+            |expr, src| {
                 // Add to imports vector and mark for deletion 
-                self.imports.push(src.to_owned());
+                move_comments(&self.comments, s.span, expr.span);
+                self.imports.push((src.to_owned(), expr.span));
                 s.expr.take();
             }
         );
@@ -380,16 +897,21 @@ pub struct TransformRequireSingleMemberVisitor {
 impl VisitMut for TransformRequireSingleMemberVisitor {
 }
 
+#[derive(Default)]
 pub struct TransformRequireFallback {
     pub imports: Vec<ModuleDecl>,
-    pub cnt: usize, // used to keep track of unnamed imports
+    pub names: NameGenerator, // used to keep track of unnamed imports
+    pub report: SharedReport,
+    pub project: Option<ProjectFile>,
 }
 
 impl TransformRequireFallback {
-    pub fn new() -> Self {
+    pub fn new(report: SharedReport, project: Option<ProjectFile>) -> Self {
         Self {
             imports: vec![],
-            cnt: 0,
+            names: NameGenerator::default(),
+            report,
+            project,
         }
     }
 }
@@ -400,7 +922,9 @@ impl VisitMut for TransformRequireFallback {
     remove_empty!();
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.names.reserve_module(m);
         m.visit_mut_children_with(self);
+        self.report.borrow_mut().fallbacks += self.imports.len();
         for decl in &self.imports {
             m.body.insert(
                 0,
@@ -409,25 +933,35 @@ impl VisitMut for TransformRequireFallback {
         }
     }
 
+    fn visit_mut_var_decl(&mut self, d: &mut VarDecl) {
+        // Kept together by `SplitRequireDeclarations` because of side effects before the require
+        if d.decls.len() > 1 {
+            return;
+        }
+        d.visit_mut_children_with(self);
+    }
+
     fn visit_mut_member_expr(&mut self, e: &mut MemberExpr) {
         e.visit_mut_children_with(self);
         // println!("Here: {:?}", e);
         if_require_call_expr(
             &e.obj.to_owned(),
-            |_expr, src| {
-                self.cnt += 1;
-                let import_ident = Ident::new(format!("_mod${}", self.cnt).into(), DUMMY_SP);
+            |expr, src| {
+                let import_ident = Ident {
+                    span: expr.span,
+                    ..self.names.ident("mod")
+                };
                 // TODO: Not sure how to get the name of the variable. Might need to add in more visitors
-                e.obj = Box::new(Expr::Ident(import_ident.to_owned()));
-                // import * as foo from 'foo';
+                *e.obj = Expr::Ident(import_ident.to_owned());
+                // import * as foo from 'foo'; or import foo from 'foo'; when `require` returned the default export
+                let specifier = if is_default_import(&self.project, &src) {
+                    ImportSpecifier::Default(ImportDefaultSpecifier { span: expr.span, local: import_ident })
+                } else {
+                    ImportSpecifier::Namespace(ImportStarAsSpecifier { span: expr.span, local: import_ident })
+                };
                 let import = ModuleDecl::Import(ImportDecl {
-                    span: DUMMY_SP,
-                    specifiers: vec![ImportSpecifier::Namespace(
-                        ImportStarAsSpecifier { 
-                            span: DUMMY_SP, 
-                            local: import_ident
-                        }
-                    )],
+                    span: expr.span,
+                    specifiers: vec![specifier],
                     src: Box::new(src.to_owned()),
                     type_only: false,
                     asserts: None,
@@ -438,23 +972,171 @@ impl VisitMut for TransformRequireFallback {
     }
 }
 
-pub struct TransformPureDestructuredRequireVisitor {
+pub struct TransformPureDestructuredRequireVisitor<C: Comments> {
+    comments: Option<C>,
+    report: SharedReport,
+    project: Option<ProjectFile>,
     imports: Vec<ModuleDecl>,
+    reassigned: HashSet<JsWord>,
+    names: NameGenerator, // used to keep track of generated locals
 }
 
-impl TransformPureDestructuredRequireVisitor {
-    pub fn new() -> Self {
+impl<C: Comments> TransformPureDestructuredRequireVisitor<C> {
+    pub fn new(comments: Option<C>, report: SharedReport, project: Option<ProjectFile>) -> Self {
         Self {
+            comments,
+            report,
+            project,
             imports: vec![],
+            reassigned: HashSet::new(),
+            names: NameGenerator::default(),
+        }
+    }
+
+    /**
+        Adds the import specifier for a single destructured property.
+        Nested patterns and bindings that get reassigned later on are imported under a generated name
+        and finished with a local declarator instead.
+     */
+    fn push_named_specifier(
+        &mut self,
+        specifiers: &mut Vec<ImportSpecifier>,
+        locals: &mut Vec<VarDeclarator>,
+        kind: VarDeclKind,
+        span: Span,
+        imported: Ident,
+        binding: &Pat,
+    ) {
+        let local = match binding {
+            Pat::Ident(BindingIdent { id, .. }) if kind == VarDeclKind::Const || !self.reassigned.contains(&id.sym) => {
+                id.to_owned()
+            },
+            _ => {
+                let local = self.names.ident(&imported.sym);
+                locals.push(VarDeclarator {
+                    span,
+                    name: binding.to_owned(),
+                    init: Some(Box::new(Expr::Ident(local.clone()))),
+                    definite: false,
+                });
+                local
+            }
+        };
+        let imported = if local.sym == imported.sym {
+            None
+        } else {
+            Some(ModuleExportName::Ident(imported))
+        };
+        specifiers.push(ImportSpecifier::Named(
+            ImportNamedSpecifier {
+                span,
+                local,
+                imported,
+                is_type_only: false
+            }
+        ));
+    }
+
+    /**
+        Converts a single `{ ... } = require('foo')` declarator into imports.
+        Returns the declarators that have to stay behind to finish the destructuring locally.
+     */
+    fn transform_declarator(&mut self, kind: VarDeclKind, decl: VarDeclarator) -> Vec<VarDeclarator> {
+        let require = match &decl.init {
+            Some(init) => if_require_call_expr(init, |expr, src| (expr.span, src)),
+            None => None,
+        };
+        let ((span, src), ObjectPat { props, .. }) = match (require, &decl.name) {
+            (Some(require), Pat::Object(pat)) => (require, pat),
+            _ => return vec![decl],
+        };
+
+        // Rest elements and defaults need the whole module object,
+        // so the pattern is kept as is and destructured from a namespace import.
+        let mut needs_namespace = props.iter().any(|prop| match prop {
+            ObjectPatProp::KeyValue(KeyValuePatProp { key, value }) => {
+                import_name_from_prop_name(key).is_none() || !matches!(**value, Pat::Ident(..) | Pat::Object(..) | Pat::Array(..))
+            },
+            ObjectPatProp::Assign(AssignPatProp { value, .. }) => value.is_some(),
+            ObjectPatProp::Rest(..) => true,
+        });
+        // In a project the module object can turn out to be the default export of the required file or package,
+        // and names a file of the project doesn't export can't be imported by name.
+        let target = interop(&self.project, &src);
+        let is_default = target == Interop::Default;
+        if let (false, Interop::Named(exports)) = (needs_namespace, target) {
+            let missing: Vec<String> = props
+                .iter()
+                .filter_map(|prop| match prop {
+                    ObjectPatProp::Assign(AssignPatProp { key, .. }) => Some(key.sym.to_string()),
+                    ObjectPatProp::KeyValue(KeyValuePatProp { key, .. }) => import_name_from_prop_name(key),
+                    ObjectPatProp::Rest(..) => None,
+                })
+                .filter(|name| !exports.named.contains(name))
+                .collect();
+            if !missing.is_empty() {
+                needs_namespace = true;
+                self.report.borrow_mut().warn(span, format!("'{}' does not export {}", src.value, missing.join(", ")));
+            }
+        }
+        if is_default || needs_namespace {
+            let local = self.names.ident(&module_name_from_src(&src.value));
+            let specifier = if is_default {
+                self.report.borrow_mut().imports.default += 1;
+                ImportSpecifier::Default(ImportDefaultSpecifier { span: decl.name.span(), local: local.clone() })
+            } else {
+                self.report.borrow_mut().fallbacks += 1;
+                ImportSpecifier::Namespace(ImportStarAsSpecifier { span: decl.name.span(), local: local.clone() })
+            };
+            self.imports.insert(0, ModuleDecl::Import(ImportDecl {
+                span,
+                specifiers: vec![specifier],
+                src: Box::new(src),
+                type_only: false,
+                asserts: None,
+            }));
+            return vec![VarDeclarator {
+                init: Some(Box::new(Expr::Ident(local))),
+                ..decl
+            }];
+        }
+
+        let mut specifiers: Vec<ImportSpecifier> = vec![];
+        let mut locals: Vec<VarDeclarator> = vec![];
+        for prop in props {
+            match prop {
+                ObjectPatProp::Assign(AssignPatProp { span, key, .. }) => {
+                    let imported = Ident::new(key.sym.to_owned(), key.span.with_ctxt(SyntaxContext::empty()));
+                    self.push_named_specifier(&mut specifiers, &mut locals, kind, *span, imported, &Pat::Ident(key.to_owned().into()));
+                },
+                ObjectPatProp::KeyValue(KeyValuePatProp { key, value }) => {
+                    let imported = Ident::new(import_name_from_prop_name(key).unwrap().into(), key.span());
+                    self.push_named_specifier(&mut specifiers, &mut locals, kind, prop.span(), imported, value);
+                },
+                ObjectPatProp::Rest(..) => unreachable!("rest patterns use a namespace import"),
+            }
         }
+        self.report.borrow_mut().imports.named += 1;
+        self.imports.insert(0, ModuleDecl::Import(ImportDecl {
+            span,
+            specifiers,
+            src: Box::new(src),
+            type_only: false,
+            asserts: None,
+        }));
+        locals
     }
 }
 
 // const { foo, bar: baz } = require('foo'); -> import { foo, bar as baz } from 'foo';
-impl VisitMut for TransformPureDestructuredRequireVisitor {
+// const { foo: { bar } } = require('foo'); -> import { foo as _foo$1 } from 'foo'; const { bar } = _foo$1;
+// const { foo, ...rest } = require('foo'); -> import * as _foo$1 from 'foo'; const { foo, ...rest } = _foo$1;
+impl<C: Comments> VisitMut for TransformPureDestructuredRequireVisitor<C> {
     remove_empty!();
 
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.reassigned = collect_reassigned_names(m);
+        self.names.reserve_module(m);
         m.visit_mut_children_with(self);
         for decl in &self.imports {
             m.body.insert(
@@ -466,92 +1148,51 @@ impl VisitMut for TransformPureDestructuredRequireVisitor {
 
     fn visit_mut_var_decl(&mut self, d: &mut VarDecl) {
         d.visit_mut_children_with(self);
-        // Remove any declarations that match the pattern `const { foo, bar: baz } = require('foo')`
-        d.decls.retain_mut(|decl| {
-            if let Pat::Invalid(_) = decl.name {
-                false
-            } else {
-                true
+        // Kept together by `SplitRequireDeclarations` because of side effects before the require
+        if d.decls.len() > 1 {
+            return;
+        }
+        let decls = d.decls.take();
+        let imports_before = self.imports.len();
+        for decl in decls {
+            let locals = self.transform_declarator(d.kind, decl);
+            d.decls.extend(locals);
+        }
+        if d.decls.is_empty() {
+            // Imports are inserted at the front and end up reversed again when added to the module,
+            // so the first import of this statement is the earliest one inserted
+            let added = self.imports.len() - imports_before;
+            if added > 0 {
+                move_comments(&self.comments, d.span, self.imports[added - 1].span());
             }
-        });
-        if d.decls.len() == 0 {
             d.take();
         }
     }
-
-    fn visit_mut_var_declarator(&mut self, d: &mut VarDeclarator) {
-        d.visit_mut_children_with(self);
-        // Remove any declarations that match the pattern `const foo = require('foo')`
-        // Rhs must be exactly the call_expr ('require');
-        if let Some(expr) = &d.init {
-            if_require_call_expr(
-                &expr.to_owned(),
-                |_expr, src| {
-                    // Lhs must be an object pattern
-                    if let Some(ObjectPat { props, .. }) = d.name.as_object() {
-                        let mut specifiers: Vec<ImportSpecifier> = vec![];
-                        if props.iter().all(|prop| {
-                            match prop {
-                                ObjectPatProp::Assign(AssignPatProp { key, value, .. }) => {
-                                    if *value == None {
-                                        specifiers.push(ImportSpecifier::Named(
-                                            ImportNamedSpecifier {
-                                                span: DUMMY_SP,
-                                                local: key.to_owned(),
-                                                imported: None,
-                                                is_type_only: false
-                                            }
-                                        ));
-                                        true
-                                    } else {
-                                        false
-                                    }
-                                },
-                                ObjectPatProp::KeyValue(v) => {
-                                    specifiers.push(ImportSpecifier::Named(
-                                        ImportNamedSpecifier {
-                                            span: DUMMY_SP,
-                                            local: Ident::new(v.value.as_ident().unwrap().to_id().0, DUMMY_SP),
-                                            imported: Some(ModuleExportName::Ident(Ident::new(v.key.as_ident().unwrap().to_id().0, DUMMY_SP))),
-                                            is_type_only: false
-                                        }
-                                    ));
-                                    true
-                                },
-                                _ => false // TODO make false
-                            }
-                        }) {
-                            // Create a new import
-                            let import = ModuleDecl::Import(ImportDecl {
-                                span: DUMMY_SP,
-                                specifiers,
-                                src: Box::new(src.to_owned()),
-                                type_only: false,
-                                asserts: None,
-                            });
-                            self.imports.insert(0, import);
-                            d.take();
-                        }
-                    }
-                }
-            );
-        }
-        
-    }
 }
 
+#[derive(Default)]
 pub struct TransformExportDefaultObject {
     pub exports: Vec<ModuleDecl>,
     pub decls: Vec<VarDeclarator>,
-    pub cnt: usize, // used to keep track of new variables
+    pub names: NameGenerator, // used to keep track of new variables
+    pub bound: HashSet<JsWord>,
+    pub repeated: HashSet<JsWord>,
+    pub exported: HashMap<JsWord, Option<JsWord>>,
+    pub report: SharedReport,
+    statement: Span,
 }
 
 impl TransformExportDefaultObject {
-    pub fn new() -> Self {
+    pub fn new(report: SharedReport) -> Self {
         Self {
             exports: vec![],
             decls: vec![],
-            cnt: 0,
+            names: NameGenerator::default(),
+            bound: HashSet::new(),
+            repeated: HashSet::new(),
+            exported: HashMap::new(),
+            report,
+            statement: DUMMY_SP,
         }
     }
 }
@@ -559,12 +1200,36 @@ impl TransformExportDefaultObject {
 impl VisitMut for TransformExportDefaultObject {
     remove_empty!();
 
+    fn visit_mut_expr_stmt(&mut self, e: &mut ExprStmt) {
+        let expr_span = e.expr.span();
+        self.statement = expr_span;
+        e.visit_mut_children_with(self);
+        // If left side is invalid then remove
+        if let Expr::Assign(AssignExpr { left: PatOrExpr::Pat(pat), .. }) = &*e.expr {
+            if let Pat::Invalid(..) = **pat {
+                // The export replaces the whole statement, so it takes over its span and with that its comments
+                for export in &mut self.exports {
+                    if let ModuleDecl::ExportNamed(export) = export {
+                        if export.span.lo == expr_span.lo {
+                            export.span = e.span;
+                        }
+                    }
+                }
+                e.expr.take();
+            }
+        }
+    }
+
     fn visit_mut_module(&mut self, m: &mut Module) {
+        self.names.reserve_module(m);
+        self.bound = collect_top_level_names(m);
+        self.repeated = collect_repeated_export_names(m);
+        self.exported = collect_exported_names(m);
         m.visit_mut_children_with(self);
         for decl in &self.decls {
             m.body.push(
                 ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
-                    span: DUMMY_SP,
+                    span: decl.span,
                     kind: VarDeclKind::Const,
                     decls: vec![decl.to_owned()],
                     declare: false,
@@ -577,13 +1242,22 @@ impl VisitMut for TransformExportDefaultObject {
     }
 
     fn visit_mut_assign_expr(&mut self, node: &mut AssignExpr) {
+        // Only assignments that make up a whole statement, `foo = module.exports = bar` is left alone
+        let is_statement = is_statement(self.statement, node);
         node.visit_mut_children_with(self);
+        if !is_statement {
+            return;
+        }
 
         if_export_default(
             &node.to_owned(), 
             || {
+                // Only `module.exports` is used for the span because the codegen looks at the source of a
+                // named export to decide on trailing commas, which the object literal might have
+                let span = node.left.span();
                 if let Some(ObjectLit {props, ..}) = node.right.as_object() {
                     let mut specifiers: Vec<ExportSpecifier> = vec![];
+                    let mut decls: Vec<VarDeclarator> = vec![];
                     let mut is_impure = false;
                     props.iter().for_each(|prop| {
                         if let PropOrSpread::Prop(prop) = prop {
@@ -594,24 +1268,23 @@ impl VisitMut for TransformExportDefaultObject {
                                         return;
                                     }
                                     // if the value is a key check if it is a valid identifier
-                                    if v.key.is_str() {
-                                        if !is_valid_identifier(&v.key.as_str().unwrap().value) {
-                                            is_impure = true;
-                                            return;
-                                        }
+                                    if v.key.is_str() && !is_valid_identifier(&v.key.as_str().unwrap().value) {
+                                        is_impure = true;
+                                        return;
                                     }
 
                                     let exported = if v.key.is_str() {
-                                        Ident::new(v.key.as_str().unwrap().value.to_owned(), DUMMY_SP)
+                                        Ident::new(v.key.as_str().unwrap().value.to_owned(), v.key.span())
                                     } else {
-                                        Ident::new(v.key.as_ident().unwrap().to_id().0, DUMMY_SP)
+                                        Ident::new(v.key.as_ident().unwrap().to_id().0, v.key.span())
                                     };
                                     
                                     match &*v.value {
-                                        Expr::Ident(ident) => {
+                                        // Globals like `{ log: console }` aren't bindings that can be exported
+                                        Expr::Ident(ident) if self.bound.contains(&ident.sym) => {
                                             specifiers.push(ExportSpecifier::Named(
                                                 ExportNamedSpecifier {
-                                                    span: DUMMY_SP,
+                                                    span: prop.span(),
                                                     orig: ModuleExportName::Ident(ident.to_owned()),
                                                     is_type_only: false,
                                                     exported: Some(ModuleExportName::Ident(exported)),
@@ -620,21 +1293,17 @@ impl VisitMut for TransformExportDefaultObject {
                                         },
                                         _ => {
                                             // extract the identifier to a new variable
-                                            self.cnt += 1;
-                                            let ident = Ident::new(
-                                                format!("_{}${}", exported.sym.to_owned(), self.cnt).as_str().into(), 
-                                                DUMMY_SP
-                                            );
+                                            let ident = self.names.ident(&exported.sym);
                                             let decl = VarDeclarator {
-                                                span: DUMMY_SP,
+                                                span: prop.span(),
                                                 name: Pat::Ident(ident.to_owned().into()),
                                                 init: Some(v.value.to_owned()),
                                                 definite: false,
                                             };
-                                            self.decls.push(decl);
+                                            decls.push(decl);
                                             specifiers.push(ExportSpecifier::Named(
                                                 ExportNamedSpecifier {
-                                                    span: DUMMY_SP,
+                                                    span: prop.span(),
                                                     orig: ModuleExportName::Ident(ident.to_owned()),
                                                     is_type_only: false,
                                                     exported: Some(ModuleExportName::Ident(exported.to_owned())),
@@ -643,11 +1312,26 @@ impl VisitMut for TransformExportDefaultObject {
                                         }
                                     }
                                 },
+                                Prop::Shorthand(v) if !self.bound.contains(&v.sym) => {
+                                    let ident = self.names.ident(&v.sym);
+                                    decls.push(VarDeclarator {
+                                        span: v.span,
+                                        name: Pat::Ident(ident.to_owned().into()),
+                                        init: Some(Box::new(Expr::Ident(v.to_owned()))),
+                                        definite: false,
+                                    });
+                                    specifiers.push(ExportSpecifier::Named(ExportNamedSpecifier {
+                                        span: v.span,
+                                        is_type_only: false,
+                                        orig: ModuleExportName::Ident(ident),
+                                        exported: Some(ModuleExportName::Ident(v.to_owned())),
+                                    }));
+                                },
                                 Prop::Shorthand(v) => {
                                     specifiers.push(ExportSpecifier::Named(ExportNamedSpecifier {
-                                        span: DUMMY_SP,
+                                        span: v.span,
                                         is_type_only: false,
-                                        orig: ModuleExportName::Ident(Ident::new(v.to_id().0, DUMMY_SP)),
+                                        orig: ModuleExportName::Ident(v.to_owned()),
                                         exported: None,
                                     }));
                                 },
@@ -658,13 +1342,52 @@ impl VisitMut for TransformExportDefaultObject {
                             }
                         }
                     });
+                    if props.iter().any(|prop| prop.is_spread()) {
+                        is_impure = true;
+                    }
+                    // `{ foo, foo: bar }` can't export `foo` twice, neither can `exports.foo = 1` elsewhere
+                    for specifier in &specifiers {
+                        if let ExportSpecifier::Named(ExportNamedSpecifier { orig, exported, .. }) = specifier {
+                            if let ModuleExportName::Ident(name) = exported.as_ref().unwrap_or(orig) {
+                                is_impure |= self.repeated.contains(&name.sym);
+                            }
+                        }
+                    }
+                    // Names exported with `export` already are left out when they export the same binding,
+                    // anything else would export them twice
+                    specifiers.retain(|specifier| {
+                        let ExportSpecifier::Named(ExportNamedSpecifier {
+                            orig: ModuleExportName::Ident(orig),
+                            exported,
+                            ..
+                        }) = specifier else {
+                            return true;
+                        };
+                        let name = match exported {
+                            Some(ModuleExportName::Ident(exported)) => &exported.sym,
+                            _ => &orig.sym,
+                        };
+                        match self.exported.get(name) {
+                            Some(Some(local)) if *local == orig.sym => false,
+                            Some(_) => {
+                                is_impure = true;
+                                true
+                            },
+                            None => true,
+                        }
+                    });
+                    // Impure objects are left for the default export visitor, which warns about them
                     if is_impure {
-                        // TODO: Emit a warning that a default export was also exported
-                    } else {
-                        node.take();
+                        return;
+                    }
+                    node.take();
+                    self.decls.extend(decls);
+                    if specifiers.is_empty() {
+                        return;
                     }
+                    self.report.borrow_mut().exports.named += specifiers.len();
                     let export = ModuleDecl::ExportNamed(NamedExport {
-                        span: DUMMY_SP,
+                        span,
                         specifiers,
                         src: None,
                         type_only: false,
@@ -675,4 +1398,1538 @@ impl VisitMut for TransformExportDefaultObject {
             }
         );
     }
-}
\ No newline at end of file
+}
+
+/**
+    Looks for requires that the other visitors would hoist above statements with side effects, ie `./config` in
+
+    ```js
+    process.env.DEBUG = '1';
+    const config = require('./config');
+    ```
+
+    where `./config` would be evaluated before `DEBUG` is set. With `SideEffects::Warn` the require is hoisted
+    anyway with a warning, with `SideEffects::CreateRequire` it stays a `require` made with `createRequire()`.
+    So do the requires of declarations `SplitRequireDeclarations` kept together, like `const start = Date.now(),
+    config = require('./config')`. Requires in functions aren't looked at, they run whenever the function is called.
+ */
+pub struct CheckSideEffectOrder {
+    pub strategy: SideEffects,
+    pub report: SharedReport,
+    pub names: NameGenerator,
+    pub declared: HashSet<JsWord>,
+    create_require: InjectedImport,
+    // The `require` that is made with `createRequire()`, once one is needed
+    require: Option<Ident>,
+}
+
+impl CheckSideEffectOrder {
+    pub fn new(strategy: SideEffects, report: SharedReport) -> Self {
+        Self {
+            strategy,
+            report,
+            names: NameGenerator::default(),
+            declared: HashSet::new(),
+            create_require: InjectedImport::new("module", "createRequire"),
+            require: None,
+        }
+    }
+
+    fn require_after_side_effects(&mut self, call: &mut CallExpr, hoisted: bool) {
+        match self.strategy {
+            SideEffects::Warn if hoisted => self.report.borrow_mut().warn(
+                call.span,
+                "`require` is hoisted above code with side effects that used to run before it, \
+                `sideEffects: \"createRequire\"` keeps it in place",
+            ),
+            // `SplitRequireDeclarations` warned about the ones it kept in place already
+            SideEffects::Warn => {},
+            SideEffects::CreateRequire => {
+                let names = &mut self.names;
+                let require = self.require.get_or_insert_with(|| names.ident("require"));
+                call.callee = Callee::Expr(Box::new(Expr::Ident(Ident { span: call.span, ..require.clone() })));
+            },
+        }
+    }
+}
+
+impl VisitMut for CheckSideEffectOrder {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.declared = collect_binding_names(m);
+        self.names.reserve_module(m);
+        self.create_require.reserve(m);
+        let mut after_side_effects = false;
+        for item in &mut m.body {
+            item.visit_mut_with(&mut HoistedRequires {
+                after_side_effects,
+                kept: false,
+                visit: &mut |call: &mut CallExpr, hoisted| self.require_after_side_effects(call, hoisted),
+            });
+            after_side_effects |= !is_pure_module_item(item);
+        }
+
+        if let Some(require) = self.require.take() {
+            // const require = createRequire(import.meta.url);
+            let create_require = self.create_require.ident(DUMMY_SP, &mut self.names, &self.declared);
+            let init = Expr::Call(CallExpr {
+                span: DUMMY_SP,
+                callee: Callee::Expr(Box::new(Expr::Ident(create_require))),
+                args: vec![import_meta(DUMMY_SP, "url").into()],
+                type_args: None,
+            });
+            let decl = VarDecl {
+                span: DUMMY_SP,
+                kind: VarDeclKind::Const,
+                declare: false,
+                decls: vec![var_declarator(DUMMY_SP, Pat::Ident(require.into()), init)],
+            };
+            m.body.insert(0, ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(decl)))));
+            self.create_require.inject(m);
+        }
+    }
+}
+
+/**
+    A name from one of node's builtin modules that generated code refers to, ie `fileURLToPath` from `url`.
+    An import of it that is already in the file is reused. Otherwise it is imported under its own name, or a
+    generated one when that is taken, once it was used.
+ */
+struct InjectedImport {
+    src: &'static str,
+    imported: &'static str,
+    local: Option<Ident>,
+    used: bool,
+}
+
+impl InjectedImport {
+    fn new(src: &'static str, imported: &'static str) -> Self {
+        Self { src, imported, local: None, used: false }
+    }
+
+    fn reserve(&mut self, m: &Module) {
+        self.local = find_named_import(m, self.src, self.imported);
+    }
+
+    fn ident(&mut self, span: Span, names: &mut NameGenerator, declared: &HashSet<JsWord>) -> Ident {
+        self.used = true;
+        let imported = self.imported;
+        let local = self.local.get_or_insert_with(|| {
+            if declared.contains(&JsWord::from(imported)) {
+                names.ident(imported)
+            } else {
+                Ident::new(imported.into(), DUMMY_SP)
+            }
+        });
+        Ident::new(local.sym.clone(), span.with_ctxt(local.span.ctxt))
+    }
+
+    fn inject(&mut self, m: &mut Module) {
+        if let (true, Some(local)) = (self.used, self.local.take()) {
+            add_named_import(m, self.src, self.imported, local);
+        }
+    }
+}
+
+/**
+    Rewrites the check for whether a file was run directly rather than required
+
+    ```js
+    if (require.main === module) {
+        main();
+    }
+    ```
+
+    to
+
+    ```js
+    import { pathToFileURL } from 'url';
+    if (import.meta.url === pathToFileURL(process.argv[1]).href) {
+        main();
+    }
+    ```
+
+    or to `if (import.meta.main)` with `MainModule::ImportMetaMain`. `module === require.main` and the negated
+    checks are rewritten the same way. Checks where `require` or `module` is a binding of the file, like the parameter
+    of `modules.map(module => require.main === module)`, are left alone.
+ */
+pub struct TransformMainModuleCheck {
+    pub strategy: MainModule,
+    pub names: NameGenerator,
+    pub declared: HashSet<JsWord>,
+    pub bindings: HashSet<Id>,
+    path_to_file_url: InjectedImport,
+}
+
+impl TransformMainModuleCheck {
+    pub fn new(strategy: MainModule) -> Self {
+        Self {
+            strategy,
+            names: NameGenerator::default(),
+            declared: HashSet::new(),
+            bindings: HashSet::new(),
+            path_to_file_url: InjectedImport::new("url", "pathToFileURL"),
+        }
+    }
+
+    fn is_main_check(&self, left: &Expr, right: &Expr) -> bool {
+        let is_main = |e: &Expr| is_free_member_of(e, "require", "main", &self.bindings);
+        let is_module = |e: &Expr| is_free_ident(e, "module", &self.bindings);
+        (is_main(left) && is_module(right)) || (is_module(left) && is_main(right))
+    }
+
+    fn main_check(&mut self, span: Span, negated: bool) -> Expr {
+        match self.strategy {
+            MainModule::ProcessArgv => {
+                // import.meta.url === pathToFileURL(process.argv[1]).href
+                let argv = Expr::Member(MemberExpr {
+                    span,
+                    obj: Box::new(Expr::Member(MemberExpr {
+                        span,
+                        obj: Box::new(Expr::Ident(Ident::new("process".into(), span))),
+                        prop: MemberProp::Ident(Ident::new("argv".into(), span)),
+                    })),
+                    prop: MemberProp::Computed(ComputedPropName {
+                        span,
+                        expr: Box::new(Expr::Lit(Lit::Num(Number { span, value: 1.0, raw: None }))),
+                    }),
+                });
+                let path_to_file_url = self.path_to_file_url.ident(span, &mut self.names, &self.declared);
+                let href = Expr::Member(MemberExpr {
+                    span,
+                    obj: Box::new(Expr::Call(CallExpr {
+                        span,
+                        callee: Callee::Expr(Box::new(Expr::Ident(path_to_file_url))),
+                        args: vec![argv.into()],
+                        type_args: None,
+                    })),
+                    prop: MemberProp::Ident(Ident::new("href".into(), span)),
+                });
+                Expr::Bin(BinExpr {
+                    span,
+                    op: if negated { BinaryOp::NotEqEq } else { BinaryOp::EqEqEq },
+                    left: Box::new(import_meta(span, "url")),
+                    right: Box::new(href),
+                })
+            },
+            MainModule::ImportMetaMain if negated => Expr::Unary(UnaryExpr {
+                span,
+                op: UnaryOp::Bang,
+                arg: Box::new(import_meta(span, "main")),
+            }),
+            MainModule::ImportMetaMain => import_meta(span, "main"),
+        }
+    }
+}
+
+impl VisitMut for TransformMainModuleCheck {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.declared = collect_binding_names(m);
+        self.bindings = collect_binding_ids(m);
+        self.names.reserve_module(m);
+        self.path_to_file_url.reserve(m);
+        m.visit_mut_children_with(self);
+        self.path_to_file_url.inject(m);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        e.visit_mut_children_with(self);
+        if let Expr::Bin(BinExpr { span, op, left, right }) = e {
+            let negated = match op {
+                BinaryOp::EqEq | BinaryOp::EqEqEq => false,
+                BinaryOp::NotEq | BinaryOp::NotEqEq => true,
+                _ => return,
+            };
+            if self.is_main_check(left, right) {
+                *e = self.main_check(*span, negated);
+            }
+        }
+    }
+}
+
+/**
+    Rewrites `require.resolve()`, which ES modules don't have, to `import.meta.resolve()`
+
+    ```js
+    const path = require.resolve('foo');
+    ```
+
+    to
+
+    ```js
+    import { fileURLToPath } from 'url';
+    const path = fileURLToPath(import.meta.resolve('foo'));
+    ```
+
+    With `RequireResolve::Url` relative paths are resolved with `new URL('./foo', import.meta.url)` instead, which
+    works before `import.meta.resolve()` does but doesn't try extensions or directory indexes, and with
+    `RequireResolve::Warn` the call is left as it is with a warning. `require.cache`, `require.main` and
+    `module.parent` have no counterpart and are left with a warning. Where `require` or `module` is a binding of
+    the file they are left alone.
+ */
+pub struct TransformRequireResolve {
+    pub strategy: RequireResolve,
+    pub report: SharedReport,
+    pub names: NameGenerator,
+    pub declared: HashSet<JsWord>,
+    pub bindings: HashSet<Id>,
+    file_url_to_path: InjectedImport,
+}
+
+impl TransformRequireResolve {
+    pub fn new(strategy: RequireResolve, report: SharedReport) -> Self {
+        Self {
+            strategy,
+            report,
+            names: NameGenerator::default(),
+            declared: HashSet::new(),
+            bindings: HashSet::new(),
+            file_url_to_path: InjectedImport::new("url", "fileURLToPath"),
+        }
+    }
+
+    fn resolve(&mut self, span: Span, spec: ExprOrSpread) -> Expr {
+        let resolved = match &*spec.expr {
+            Expr::Lit(Lit::Str(src)) if self.strategy == RequireResolve::Url && is_relative(&src.value) => {
+                Expr::New(NewExpr {
+                    span,
+                    callee: Box::new(Expr::Ident(Ident::new("URL".into(), span))),
+                    args: Some(vec![spec, import_meta(span, "url").into()]),
+                    type_args: None,
+                })
+            },
+            _ => Expr::Call(CallExpr {
+                span,
+                callee: Callee::Expr(Box::new(import_meta(span, "resolve"))),
+                args: vec![spec],
+                type_args: None,
+            }),
+        };
+        let file_url_to_path = self.file_url_to_path.ident(span, &mut self.names, &self.declared);
+        Expr::Call(CallExpr {
+            span,
+            callee: Callee::Expr(Box::new(Expr::Ident(file_url_to_path))),
+            args: vec![resolved.into()],
+            type_args: None,
+        })
+    }
+}
+
+impl VisitMut for TransformRequireResolve {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.declared = collect_binding_names(m);
+        self.bindings = collect_binding_ids(m);
+        self.names.reserve_module(m);
+        self.file_url_to_path.reserve(m);
+        m.visit_mut_children_with(self);
+        self.file_url_to_path.inject(m);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        e.visit_mut_children_with(self);
+        if let Expr::Call(CallExpr { span, callee: Callee::Expr(callee), args, .. }) = e {
+            if !is_free_member_of(callee, "require", "resolve", &self.bindings) {
+                return;
+            }
+            if self.strategy == RequireResolve::Warn {
+                self.report
+                    .borrow_mut()
+                    .warn(*span, "`require.resolve` was left as it is, browsers have no file paths to resolve to");
+                return;
+            }
+            // `require.resolve('foo', { paths })` looks in other directories, which `import.meta.resolve()` can't
+            if args.len() != 1 || args[0].spread.is_some() {
+                self.report
+                    .borrow_mut()
+                    .warn(*span, "`require.resolve` with options was left as it is, ES modules don't have `require`");
+                return;
+            }
+            *e = self.resolve(*span, args.remove(0));
+        }
+    }
+
+    fn visit_mut_member_expr(&mut self, e: &mut MemberExpr) {
+        e.visit_mut_children_with(self);
+        let (Expr::Ident(obj), MemberProp::Ident(prop)) = (&*e.obj, &e.prop) else {
+            return;
+        };
+        if self.bindings.contains(&obj.to_id()) {
+            return;
+        }
+        let message = match (&*obj.sym, &*prop.sym) {
+            ("require", "cache") => {
+                "`require.cache` has no ESM counterpart, ES modules can't be removed from the module cache"
+            },
+            // `TransformMainModuleCheck` converted the checks against `module` already
+            ("require", "main") => "`require.main` has no ESM counterpart and was left as it is",
+            ("module", "parent") => {
+                "`module.parent` has no ESM counterpart, ES modules don't know which module imported them"
+            },
+            _ => return,
+        };
+        self.report.borrow_mut().warn(e.span, message);
+    }
+}
+
+/**
+    Rewrites `__dirname` and `__filename`, which ES modules don't have
+
+    ```js
+    const config = path.join(__dirname, 'config.json');
+    ```
+
+    to
+
+    ```js
+    import { fileURLToPath } from 'url';
+    import { dirname } from 'path';
+    const __filename = fileURLToPath(import.meta.url);
+    const __dirname = dirname(__filename);
+    const config = path.join(__dirname, 'config.json');
+    ```
+
+    Only the ones the file uses are declared. With `Dirname::ImportMeta` they become `import.meta.dirname` and
+    `import.meta.filename` instead, and with `Dirname::Warn` they are left as they are with a warning. Where they are
+    bindings of the file they are left alone.
+ */
+pub struct TransformDirname {
+    pub strategy: Dirname,
+    pub report: SharedReport,
+    pub names: NameGenerator,
+    pub declared: HashSet<JsWord>,
+    pub bindings: HashSet<Id>,
+    uses_filename: bool,
+    uses_dirname: bool,
+    // Of the free references, which the declarations have to share to be what they refer to
+    unresolved: SyntaxContext,
+    file_url_to_path: InjectedImport,
+    dirname: InjectedImport,
+}
+
+impl TransformDirname {
+    pub fn new(strategy: Dirname, report: SharedReport) -> Self {
+        Self {
+            strategy,
+            report,
+            names: NameGenerator::default(),
+            declared: HashSet::new(),
+            bindings: HashSet::new(),
+            uses_filename: false,
+            uses_dirname: false,
+            unresolved: SyntaxContext::empty(),
+            file_url_to_path: InjectedImport::new("url", "fileURLToPath"),
+            dirname: InjectedImport::new("path", "dirname"),
+        }
+    }
+
+    // What a free `__dirname` or `__filename` is replaced with, if anything
+    fn replacement(&mut self, ident: &Ident) -> Option<Expr> {
+        let prop = match &*ident.sym {
+            "__dirname" => "dirname",
+            "__filename" => "filename",
+            _ => return None,
+        };
+        if self.bindings.contains(&ident.to_id()) {
+            return None;
+        }
+        self.unresolved = ident.span.ctxt;
+        match self.strategy {
+            Dirname::Url if prop == "dirname" => self.uses_dirname = true,
+            Dirname::Url => self.uses_filename = true,
+            Dirname::ImportMeta => return Some(import_meta(ident.span, prop)),
+            Dirname::Warn => self
+                .report
+                .borrow_mut()
+                .warn(ident.span, format!("`{}` was left as it is, browsers have no file paths", ident.sym)),
+        }
+        None
+    }
+
+    // `const __filename = fileURLToPath(import.meta.url);` and `const __dirname = dirname(__filename);`
+    fn declarations(&mut self) -> Vec<ModuleItem> {
+        let mut decls = vec![];
+        let file_url_to_path = self.file_url_to_path.ident(DUMMY_SP, &mut self.names, &self.declared);
+        let filename = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Ident(file_url_to_path))),
+            args: vec![import_meta(DUMMY_SP, "url").into()],
+            type_args: None,
+        });
+        let filename = if self.uses_filename {
+            let ident = Ident::new("__filename".into(), DUMMY_SP.with_ctxt(self.unresolved));
+            decls.push(var_declarator(DUMMY_SP, Pat::Ident(ident.clone().into()), filename));
+            Expr::Ident(ident)
+        } else {
+            filename
+        };
+        if self.uses_dirname {
+            let dirname = self.dirname.ident(DUMMY_SP, &mut self.names, &self.declared);
+            let init = Expr::Call(CallExpr {
+                span: DUMMY_SP,
+                callee: Callee::Expr(Box::new(Expr::Ident(dirname))),
+                args: vec![filename.into()],
+                type_args: None,
+            });
+            let ident = Ident::new("__dirname".into(), DUMMY_SP.with_ctxt(self.unresolved));
+            decls.push(var_declarator(DUMMY_SP, Pat::Ident(ident.into()), init));
+        }
+        decls
+            .into_iter()
+            .map(|decl| {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                    span: DUMMY_SP,
+                    kind: VarDeclKind::Const,
+                    declare: false,
+                    decls: vec![decl],
+                }))))
+            })
+            .collect()
+    }
+}
+
+impl VisitMut for TransformDirname {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.declared = collect_binding_names(m);
+        self.bindings = collect_binding_ids(m);
+        self.names.reserve_module(m);
+        self.file_url_to_path.reserve(m);
+        self.dirname.reserve(m);
+        m.visit_mut_children_with(self);
+        if self.uses_filename || self.uses_dirname {
+            let decls = self.declarations();
+            // Right after the imports, before anything that could use them
+            let at = m.body
+                .iter()
+                .position(|item| !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(..))))
+                .unwrap_or(m.body.len());
+            m.body.splice(at..at, decls);
+        }
+        self.dirname.inject(m);
+        self.file_url_to_path.inject(m);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        e.visit_mut_children_with(self);
+        if let Expr::Ident(ident) = e {
+            if let Some(replacement) = self.replacement(ident) {
+                *e = replacement;
+            }
+        }
+    }
+
+    // `{ __dirname }`
+    fn visit_mut_prop(&mut self, p: &mut Prop) {
+        p.visit_mut_children_with(self);
+        if let Prop::Shorthand(ident) = p {
+            if let Some(replacement) = self.replacement(ident) {
+                *p = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.take()), value: Box::new(replacement) });
+            }
+        }
+    }
+}
+
+/**
+    Converts requires of relative template literals to a lookup in an eager `import.meta.glob()`
+
+    ```js
+    const messages = require(`./locales/messages.${lang}.json`);
+    ```
+
+    to
+
+    ```js
+    const messages = import.meta.glob('./locales/messages.*.json', { eager: true })[`./locales/messages.${lang}.json`];
+    ```
+
+    The glob is the template with a `*` in place of each expression, so expressions can't span directories.
+    Templates without a file extension are left alone with a warning, since the keys of the glob are the full
+    file names. Calls of a `require` that is a binding of the file are left alone.
+ */
+pub struct TransformTemplateRequireToGlob {
+    pub report: SharedReport,
+    pub bindings: HashSet<Id>,
+}
+
+impl TransformTemplateRequireToGlob {
+    pub fn new(report: SharedReport) -> Self {
+        Self { report, bindings: HashSet::new() }
+    }
+
+    // `./locales/messages.*.json` for `./locales/messages.${lang}.json`
+    fn glob_pattern(tpl: &Tpl) -> Option<String> {
+        let mut pattern = String::new();
+        for quasi in &tpl.quasis {
+            let part = quasi.cooked.as_ref()?;
+            if part.contains(['*', '?', '[', ']', '{', '}', '!']) {
+                return None;
+            }
+            pattern.push_str(part);
+            if !quasi.tail && !pattern.ends_with('*') {
+                pattern.push('*');
+            }
+        }
+        Some(pattern)
+    }
+}
+
+impl VisitMut for TransformTemplateRequireToGlob {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.bindings = collect_binding_ids(m);
+        m.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        e.visit_mut_children_with(self);
+        let Expr::Call(CallExpr { span, callee: Callee::Expr(callee), args, .. }) = e else {
+            return;
+        };
+        if !is_free_ident(callee, "require", &self.bindings) || args.len() != 1 {
+            return;
+        }
+        let Expr::Tpl(tpl) = &*args[0].expr else {
+            return;
+        };
+        if tpl.exprs.is_empty() || args[0].spread.is_some() {
+            return;
+        }
+        let Some(pattern) = Self::glob_pattern(tpl).filter(|pattern| is_relative(pattern)) else {
+            return;
+        };
+        let file_name = pattern.rsplit('/').next().unwrap_or_default();
+        if !file_name.contains('.') || file_name.ends_with('*') {
+            self.report.borrow_mut().warn(
+                *span,
+                "`require` of a template literal was left as it is, `import.meta.glob()` needs a file extension",
+            );
+            return;
+        }
+
+        // import.meta.glob('./locales/*.json', { eager: true })[`./locales/${lang}.json`]
+        let options = Expr::Object(ObjectLit {
+            span: *span,
+            props: vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(Ident::new("eager".into(), *span)),
+                value: Box::new(Expr::Lit(Lit::Bool(Bool { span: *span, value: true }))),
+            })))],
+        });
+        let glob = Expr::Call(CallExpr {
+            span: *span,
+            callee: Callee::Expr(Box::new(import_meta(*span, "glob"))),
+            args: vec![Expr::Lit(Lit::Str(quote_str(*span, &pattern, '\''))).into(), options.into()],
+            type_args: None,
+        });
+        let key = args.remove(0).expr;
+        *e = Expr::Member(MemberExpr {
+            span: *span,
+            obj: Box::new(glob),
+            prop: MemberProp::Computed(ComputedPropName { span: *span, expr: key }),
+        });
+        self.report.borrow_mut().imports.glob += 1;
+    }
+}
+
+/**
+    Finds the `require` calls of a statement that come after code with side effects: the ones that end up hoisted,
+    the same ones the import visitors convert, in statements after that code, and all of those in a declaration
+    `SplitRequireDeclarations` kept together, which stay where they are.
+ */
+struct HoistedRequires<'a> {
+    after_side_effects: bool,
+    // In a declaration that was kept together
+    kept: bool,
+    // Called with whether the `require` ends up hoisted
+    visit: &'a mut dyn FnMut(&mut CallExpr, bool),
+}
+
+impl HoistedRequires<'_> {
+    fn visit_require(&mut self, e: &mut Expr) -> bool {
+        if if_require_call_expr(e, |_, _| ()).is_none() {
+            return false;
+        }
+        if let (Expr::Call(call), true) = (e, self.kept || self.after_side_effects) {
+            (self.visit)(call, !self.kept);
+        }
+        true
+    }
+}
+
+impl VisitMut for HoistedRequires<'_> {
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        if !(self.kept && self.visit_require(e)) {
+            e.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_expr_stmt(&mut self, s: &mut ExprStmt) {
+        if !self.visit_require(&mut s.expr) {
+            s.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_var_decl(&mut self, d: &mut VarDecl) {
+        // Kept together by `SplitRequireDeclarations`, so nothing in it is hoisted
+        if d.decls.len() > 1 {
+            let kept = std::mem::replace(&mut self.kept, true);
+            d.visit_mut_children_with(self);
+            self.kept = kept;
+            return;
+        }
+        for decl in &mut d.decls {
+            if !decl.init.as_deref_mut().is_some_and(|init| self.visit_require(init)) {
+                decl.visit_mut_children_with(self);
+            }
+        }
+    }
+
+    fn visit_mut_member_expr(&mut self, e: &mut MemberExpr) {
+        if !self.visit_require(&mut e.obj) {
+            e.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_function(&mut self, _: &mut Function) {}
+
+    fn visit_mut_arrow_expr(&mut self, _: &mut ArrowExpr) {}
+
+    fn visit_mut_class(&mut self, _: &mut Class) {}
+}
+
+/**
+    Puts the imports the other visitors hoisted to the top of the file back in the order of the `require` calls
+    they came from. Each visitor adds its imports in front of the ones before it, and since ES modules are evaluated
+    in the order they are imported, that would run the required files in a different order than before.
+ */
+#[derive(Default)]
+pub struct SortHoistedImports;
+
+impl VisitMut for SortHoistedImports {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        let hoisted = m
+            .body
+            .iter()
+            .take_while(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(..))))
+            .count();
+        m.body[..hoisted].sort_by_key(|item| item.span().lo);
+    }
+}
+
+/**
+    Merges imports into an earlier import of the same file, which is where the imports of partially migrated files
+    end up next to the ones converted from `require`
+
+    ```js
+    import { readFile } from 'fs';
+    import { writeFile } from 'fs';
+    import config from './config';
+    import * as helpers from './config';
+    ```
+
+    to
+
+    ```js
+    import { readFile, writeFile } from 'fs';
+    import config, * as helpers from './config';
+    ```
+
+    Imports that can't be combined into one, like a namespace and named imports, are left as they are.
+    A second default or namespace import of the same file is the same module object, so its references use the
+    first one instead, ie `import cjs from 'cjs-pkg'; import _cjsPkg$1 from 'cjs-pkg';` is left with `cjs`.
+    This is skipped when the name of the first one is declared elsewhere in the file, where it could be shadowed.
+    Evaluation order doesn't change, a file is evaluated where it is first imported either way.
+ */
+pub struct MergeImports<C: Comments> {
+    pub comments: Option<C>,
+    pub bindings: HashSet<Id>,
+    // Locals of repeated default and namespace imports, to the local of the first one
+    renamed: HashMap<Id, Ident>,
+}
+
+impl<C: Comments> MergeImports<C> {
+    pub fn new(comments: Option<C>) -> Self {
+        Self { comments, bindings: HashSet::new(), renamed: HashMap::new() }
+    }
+
+    // Whether references to `local` can't end up at another binding of the same name
+    fn is_unshadowed(&self, local: &Ident) -> bool {
+        !self.bindings.iter().any(|(sym, ctxt)| *sym == local.sym && *ctxt != local.span.ctxt)
+    }
+
+    // Drops the default or namespace import of `from` that `into` has already, to be renamed to the one of `into`
+    fn reuse_module_object(&mut self, into: &ImportDecl, from: &mut ImportDecl) {
+        let module_object = |s: &ImportSpecifier| match s {
+            ImportSpecifier::Default(ImportDefaultSpecifier { local, .. }) => Some((true, local.clone())),
+            ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => Some((false, local.clone())),
+            ImportSpecifier::Named(..) => None,
+        };
+        let Some((is_default, existing)) = into.specifiers.iter().find_map(module_object) else {
+            return;
+        };
+        if !self.is_unshadowed(&existing) {
+            return;
+        }
+        from.specifiers.retain(|s| match module_object(s) {
+            Some((same_kind, local)) if same_kind == is_default => {
+                self.renamed.insert(local.to_id(), existing.clone());
+                false
+            },
+            _ => true,
+        });
+    }
+
+    // Moves the specifiers of `from` over to `into` when one import can hold all of them
+    fn merge(&mut self, into: &mut ImportDecl, from: &mut ImportDecl) -> bool {
+        if into.type_only || from.type_only || into.asserts.is_some() || from.asserts.is_some() {
+            return false;
+        }
+        let had_specifiers = !from.specifiers.is_empty();
+        self.reuse_module_object(into, from);
+        // Nothing is left that the earlier import doesn't bind already
+        if had_specifiers && from.specifiers.is_empty() {
+            return true;
+        }
+        let has_default = |d: &ImportDecl| d.specifiers.iter().any(|s| matches!(s, ImportSpecifier::Default(..)));
+        let has_namespace = |d: &ImportDecl| d.specifiers.iter().any(|s| matches!(s, ImportSpecifier::Namespace(..)));
+        let has_named = |d: &ImportDecl| d.specifiers.iter().any(|s| matches!(s, ImportSpecifier::Named(..)));
+        // `import foo, * as bar` and `import foo, { bar }` are fine, a namespace and names can't go together
+        let clashes = (has_default(into) && has_default(from))
+            || (has_namespace(into) && has_namespace(from))
+            || ((has_namespace(into) || has_namespace(from)) && (has_named(into) || has_named(from)));
+        if clashes {
+            return false;
+        }
+        into.specifiers.append(&mut from.specifiers);
+        // The default import has to come first
+        into.specifiers.sort_by_key(|s| !matches!(s, ImportSpecifier::Default(..)));
+        true
+    }
+}
+
+impl<C: Comments> VisitMut for MergeImports<C> {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.bindings = collect_binding_ids(m);
+        let mut merged: Vec<ModuleItem> = Vec::with_capacity(m.body.len());
+        for mut item in m.body.take() {
+            if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = &mut item {
+                let merged_into = merged.iter_mut().find_map(|item| match item {
+                    ModuleItem::ModuleDecl(ModuleDecl::Import(earlier)) if earlier.src.value == import.src.value => {
+                        self.merge(earlier, import).then_some(earlier.span)
+                    },
+                    _ => None,
+                });
+                if let Some(span) = merged_into {
+                    if let Some(comments) = &self.comments {
+                        move_comments(comments, import.span, span);
+                    }
+                    continue;
+                }
+            }
+            merged.push(item);
+        }
+        m.body = merged;
+        if !self.renamed.is_empty() {
+            m.visit_mut_with(&mut RenameReferences { renamed: &self.renamed });
+        }
+    }
+}
+
+/**
+    Points the references to some bindings at others, keeping what is exported or used as a property key the same:
+    `{ foo }` becomes `{ foo: bar }` and `export { foo }` becomes `export { bar as foo }`.
+ */
+struct RenameReferences<'a> {
+    renamed: &'a HashMap<Id, Ident>,
+}
+
+impl RenameReferences<'_> {
+    fn rename(&self, ident: &Ident) -> Option<Ident> {
+        self.renamed.get(&ident.to_id()).map(|to| Ident::new(to.sym.clone(), ident.span.with_ctxt(to.span.ctxt)))
+    }
+}
+
+impl VisitMut for RenameReferences<'_> {
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        if let Some(renamed) = self.rename(ident) {
+            *ident = renamed;
+        }
+    }
+
+    // Only computed keys refer to bindings, `foo.bar` and `{ bar: 1 }` don't
+    fn visit_mut_member_prop(&mut self, p: &mut MemberProp) {
+        if let MemberProp::Computed(..) = p {
+            p.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_prop_name(&mut self, p: &mut PropName) {
+        if let PropName::Computed(..) = p {
+            p.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_prop(&mut self, p: &mut Prop) {
+        if let Prop::Shorthand(ident) = p {
+            if let Some(renamed) = self.rename(ident) {
+                *p = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.take()), value: Box::new(Expr::Ident(renamed)) });
+            }
+            return;
+        }
+        p.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_export_named_specifier(&mut self, s: &mut ExportNamedSpecifier) {
+        if let ModuleExportName::Ident(orig) = &mut s.orig {
+            if let Some(renamed) = self.rename(orig) {
+                s.exported.get_or_insert_with(|| ModuleExportName::Ident(orig.clone()));
+                *orig = renamed;
+            }
+        }
+    }
+}
+
+/**
+    Puts the module specifiers of imports and exports in the quotes asked for with `quotes_style`, escaping them
+    as needed. `QuotesStyle::Preserve` leaves them as they are.
+ */
+pub struct NormalizeQuotes {
+    pub style: QuotesStyle,
+}
+
+impl NormalizeQuotes {
+    fn requote(&self, src: &mut Str) {
+        let quote = match self.style {
+            QuotesStyle::Preserve => return,
+            QuotesStyle::Single => '\'',
+            QuotesStyle::Double => '"',
+        };
+        *src = quote_str(src.span, &src.value, quote);
+    }
+}
+
+impl VisitMut for NormalizeQuotes {
+    fn visit_mut_import_decl(&mut self, d: &mut ImportDecl) {
+        self.requote(&mut d.src);
+    }
+
+    fn visit_mut_named_export(&mut self, e: &mut NamedExport) {
+        if let Some(src) = &mut e.src {
+            self.requote(src);
+        }
+    }
+
+    fn visit_mut_export_all(&mut self, e: &mut ExportAll) {
+        self.requote(&mut e.src);
+    }
+}
+
+/**
+    Counts the `require`, `module` and `exports` references that are still left once the other visitors are done.
+    References to a binding of the file with one of these names, like a `require` parameter, are not counted.
+ */
+pub struct CountRemainingReferences {
+    pub report: SharedReport,
+    pub bindings: HashSet<Id>,
+}
+
+impl CountRemainingReferences {
+    pub fn new(report: SharedReport) -> Self {
+        Self { report, bindings: HashSet::new() }
+    }
+}
+
+impl VisitMut for CountRemainingReferences {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.bindings = collect_binding_ids(m);
+        m.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_expr(&mut self, e: &mut Expr) {
+        e.visit_mut_children_with(self);
+        if let Expr::Ident(ident) = e {
+            if self.bindings.contains(&ident.to_id()) {
+                return;
+            }
+            let mut report = self.report.borrow_mut();
+            match &*ident.sym {
+                "require" => report.remaining_requires += 1,
+                "module" | "exports" => report.remaining_module_refs += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+/**
+    Adds the report as a line comment in front of everything else in the file,
+    which is how it gets out of the wasm plugin and over to mdnzr.
+ */
+pub struct EmitReportComment<C: Comments> {
+    pub comments: Option<C>,
+    pub report: SharedReport,
+}
+
+impl<C: Comments> EmitReportComment<C> {
+    pub fn new(comments: Option<C>, report: SharedReport) -> Self {
+        Self { comments, report }
+    }
+}
+
+impl<C: Comments> VisitMut for EmitReportComment<C> {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        let pos = m.body
+            .first()
+            .map(|item| item.span().lo)
+            .filter(|pos| !pos.is_dummy())
+            .unwrap_or(m.span.lo);
+        let report: &ConversionReport = &self.report.borrow();
+        let mut comments = vec![Comment {
+            kind: CommentKind::Line,
+            span: DUMMY_SP,
+            text: report.to_comment_text().into(),
+        }];
+        comments.extend(self.comments.take_leading(pos).unwrap_or_default());
+        self.comments.add_leading_comments(pos, comments);
+    }
+}
+
+/**
+    Rewrites relative specifiers of imports and exports to the files they resolve to, ie `./foo` to `./foo.js`,
+    optionally with the extension the files will have once converted. Imports of specifiers that turn out to be
+    JSON files are made default imports.
+ */
+pub struct ResolveRelativeSpecifiers {
+    pub importer: PathBuf,
+    pub extension: Option<String>,
+    pub report: SharedReport,
+    names: NameGenerator,
+    // Destructuring of the named imports of JSON files, goes after the imports
+    json_decls: Vec<ModuleItem>,
+}
+
+impl ResolveRelativeSpecifiers {
+    pub fn new(importer: PathBuf, extension: Option<String>, report: SharedReport) -> Self {
+        Self { importer, extension, report, names: NameGenerator::default(), json_decls: vec![] }
+    }
+
+    /**
+        A specifier like `./settings` can turn out to be a JSON file, which only has a default export.
+        Namespace imports of it become default imports, and named imports are destructured from the default one.
+     */
+    fn import_json_as_default(&mut self, d: &mut ImportDecl) {
+        let mut default: Option<Ident> = None;
+        let mut aliases: Vec<Ident> = vec![];
+        let mut props: Vec<ObjectPatProp> = vec![];
+        let mut kept: Vec<ImportSpecifier> = vec![];
+        for specifier in d.specifiers.take() {
+            match specifier {
+                ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
+                | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) if default.is_none() => {
+                    default = Some(local);
+                },
+                ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
+                | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => aliases.push(local),
+                ImportSpecifier::Named(ImportNamedSpecifier { span, local, imported, is_type_only: false }) => {
+                    props.push(match imported {
+                        None => ObjectPatProp::Assign(AssignPatProp { span, key: local, value: None }),
+                        Some(imported) => ObjectPatProp::KeyValue(KeyValuePatProp {
+                            key: match imported {
+                                ModuleExportName::Ident(ident) => PropName::Ident(ident),
+                                ModuleExportName::Str(str) => PropName::Str(str),
+                            },
+                            value: Box::new(Pat::Ident(local.into())),
+                        }),
+                    });
+                },
+                specifier => kept.push(specifier),
+            }
+        }
+        if default.is_none() && !props.is_empty() {
+            default = Some(self.names.ident(&module_name_from_src(&d.src.value)));
+        }
+        let Some(default) = default else {
+            d.specifiers = kept;
+            return;
+        };
+
+        let mut decls: Vec<VarDeclarator> = aliases
+            .into_iter()
+            .map(|alias| var_declarator(alias.span, Pat::Ident(alias.into()), Expr::Ident(default.clone())))
+            .collect();
+        if !props.is_empty() {
+            let pat = Pat::Object(ObjectPat { span: DUMMY_SP, props, optional: false, type_ann: None });
+            decls.push(var_declarator(DUMMY_SP, pat, Expr::Ident(default.clone())));
+        }
+        // One statement per declarator, like `SplitRequireDeclarations` leaves them
+        self.json_decls.extend(decls.into_iter().map(|decl| {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                span: decl.span,
+                kind: VarDeclKind::Const,
+                declare: false,
+                decls: vec![decl],
+            }))))
+        }));
+        d.specifiers = vec![ImportSpecifier::Default(ImportDefaultSpecifier { span: default.span, local: default })];
+        d.specifiers.extend(kept);
+    }
+
+    fn rewrite(&mut self, src: &mut Str) {
+        if !is_relative(&src.value) {
+            return;
+        }
+        match rewrite_specifier(&self.importer, &src.value, self.extension.as_deref(), |path| path.is_file()) {
+            Some(specifier) if *src.value != specifier => {
+                *src = quote_str(src.span, &specifier, quote_of(src));
+            },
+            Some(..) => {},
+            None => {
+                self.report.borrow_mut().warn(src.span, format!("could not resolve '{}'", src.value));
+            },
+        }
+    }
+}
+
+impl VisitMut for ResolveRelativeSpecifiers {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.names.reserve_module(m);
+        m.visit_mut_children_with(self);
+        if !self.json_decls.is_empty() {
+            let at = m.body
+                .iter()
+                .position(|item| !matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(..))))
+                .unwrap_or(m.body.len());
+            m.body.splice(at..at, self.json_decls.drain(..));
+        }
+    }
+
+    fn visit_mut_import_decl(&mut self, d: &mut ImportDecl) {
+        self.rewrite(&mut d.src);
+        if is_json(&d.src.value) && !d.type_only {
+            self.import_json_as_default(d);
+        }
+    }
+
+    fn visit_mut_named_export(&mut self, e: &mut NamedExport) {
+        if let Some(src) = &mut e.src {
+            self.rewrite(src);
+        }
+    }
+
+    fn visit_mut_export_all(&mut self, e: &mut ExportAll) {
+        self.rewrite(&mut e.src);
+    }
+
+    // import('./foo')
+    fn visit_mut_call_expr(&mut self, e: &mut CallExpr) {
+        e.visit_mut_children_with(self);
+        if let (Callee::Import(..), Some(ExprOrSpread { spread: None, expr })) = (&e.callee, e.args.first_mut()) {
+            if let Expr::Lit(Lit::Str(src)) = &mut **expr {
+                self.rewrite(src);
+            }
+        }
+    }
+}
+
+/**
+    Adds `with { type: 'json' }` to imports and re-exports of JSON files, which node refuses to load without it.
+    The attribute is quoted like the specifier. Ones that have attributes already are left as they are.
+ */
+pub struct AddJsonImportAttributes;
+
+impl AddJsonImportAttributes {
+    fn attributes(src: &Str) -> Option<Box<ObjectLit>> {
+        if !is_json(&src.value) {
+            return None;
+        }
+        let prop = Prop::KeyValue(KeyValueProp {
+            key: PropName::Ident(Ident::new("type".into(), DUMMY_SP)),
+            value: Box::new(Expr::Lit(Lit::Str(quote_str(DUMMY_SP, "json", quote_of(src))))),
+        });
+        Some(Box::new(ObjectLit { span: DUMMY_SP, props: vec![PropOrSpread::Prop(Box::new(prop))] }))
+    }
+}
+
+impl VisitMut for AddJsonImportAttributes {
+    fn visit_mut_import_decl(&mut self, d: &mut ImportDecl) {
+        if !d.type_only && d.asserts.is_none() {
+            d.asserts = Self::attributes(&d.src);
+        }
+    }
+
+    fn visit_mut_named_export(&mut self, e: &mut NamedExport) {
+        if let (false, None, Some(src)) = (e.type_only, &e.asserts, &e.src) {
+            e.asserts = Self::attributes(src);
+        }
+    }
+}
+
+/**
+    Turns imports back into `require` calls where they stand, the way they'd be written by hand.
+
+    `import * as foo from 'foo'` and `import foo from 'foo'` become `const foo = require('foo')`,
+    `import { foo, bar as baz } from 'foo'` becomes `const { foo, bar: baz } = require('foo')`
+    and `import 'foo'` becomes `require('foo')`. `import type` declarations are left for the TypeScript compiler.
+    Type only specifiers are dropped, so an import made of nothing else, like `import { type Foo } from 'foo'`,
+    is removed altogether, the way the TypeScript compiler elides it.
+ */
+#[derive(Default)]
+pub struct TransformImportToRequireVisitor {
+    pub report: SharedReport,
+}
+
+impl TransformImportToRequireVisitor {
+    pub fn new(report: SharedReport) -> Self {
+        Self { report }
+    }
+
+    fn transform_import(&mut self, import: ImportDecl) -> Vec<ModuleItem> {
+        let ImportDecl { span, specifiers, src, .. } = import;
+        if specifiers.is_empty() {
+            return vec![ModuleItem::Stmt(Stmt::Expr(ExprStmt { span, expr: Box::new(require_call(*src)) }))];
+        }
+
+        let mut module: Option<Ident> = None;
+        let mut decls: Vec<VarDeclarator> = vec![];
+        let mut props: Vec<ObjectPatProp> = vec![];
+        for specifier in specifiers {
+            match specifier {
+                ImportSpecifier::Default(ImportDefaultSpecifier { local, .. })
+                | ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => {
+                    // `import foo, * as bar from 'foo'` binds the same module object twice
+                    let init = match &module {
+                        Some(module) => Expr::Ident(module.clone()),
+                        None => require_call(*src.clone()),
+                    };
+                    module.get_or_insert(local.clone());
+                    decls.push(var_declarator(span, Pat::Ident(local.into()), init));
+                },
+                ImportSpecifier::Named(ImportNamedSpecifier { is_type_only: true, .. }) => {},
+                ImportSpecifier::Named(ImportNamedSpecifier { span, local, imported, .. }) => {
+                    let key = match imported {
+                        Some(ModuleExportName::Ident(imported)) if imported.sym != local.sym => PropName::Ident(imported),
+                        Some(ModuleExportName::Str(imported)) => PropName::Str(imported),
+                        _ => {
+                            props.push(ObjectPatProp::Assign(AssignPatProp { span, key: local, value: None }));
+                            continue;
+                        },
+                    };
+                    props.push(ObjectPatProp::KeyValue(KeyValuePatProp {
+                        key,
+                        value: Box::new(Pat::Ident(local.into())),
+                    }));
+                },
+            }
+        }
+        // Named imports get a require of their own, `import foo, { bar }` to `const foo = require('foo');
+        // const { bar } = require('foo');`, which converts back to named imports rather than a destructured `foo`
+        if !props.is_empty() {
+            let pat = Pat::Object(ObjectPat { span, props, optional: false, type_ann: None });
+            decls.push(var_declarator(span, pat, require_call(*src)));
+        }
+        // One statement per binding
+        decls
+            .into_iter()
+            .map(|decl| {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(VarDecl {
+                    span,
+                    kind: VarDeclKind::Const,
+                    declare: false,
+                    decls: vec![decl],
+                }))))
+            })
+            .collect()
+    }
+}
+
+impl VisitMut for TransformImportToRequireVisitor {
+    // Only imports at the top-level, the ones in `declare module` blocks are types
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        m.body = m
+            .body
+            .take()
+            .into_iter()
+            .flat_map(|item| match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import)) if !import.type_only => self.transform_import(import),
+                item => vec![item],
+            })
+            .collect();
+    }
+}
+
+/**
+    Turns exports back into assignments to `exports` and `module.exports`.
+
+    `export const foo = 123` becomes `exports.foo = 123` when nothing else refers to `foo`, otherwise the declaration
+    is kept and followed by `exports.foo = foo`, as are exported functions and classes.
+    `export { foo as bar }` becomes `exports.bar = foo`, or `module.exports = { bar: foo }` when it lists everything
+    the module exports. `export default foo` becomes `module.exports = foo`, or `exports.default = foo` when the module
+    has named exports as well.
+ */
+#[derive(Default)]
+pub struct TransformExportToAssignmentVisitor {
+    pub report: SharedReport,
+    values: HashSet<JsWord>,
+    reassigned: HashSet<JsWord>,
+    has_named_exports: bool,
+    export_count: usize,
+}
+
+impl TransformExportToAssignmentVisitor {
+    pub fn new(report: SharedReport) -> Self {
+        Self { report, ..Default::default() }
+    }
+
+    // exports.foo = foo;
+    fn assign_export(&mut self, span: Span, name: &str, value: Expr) -> ModuleItem {
+        if name == "default" {
+            return self.assign_default_export(span, value);
+        }
+        if let Expr::Ident(ident) = &value {
+            if self.reassigned.contains(&ident.sym) {
+                self.report
+                    .borrow_mut()
+                    .warn(span, format!("`{}` is reassigned after it is exported, `exports.{}` won't follow it", ident.sym, name));
+            }
+        }
+        assign_stmt(span, exports_member(span, name), value)
+    }
+
+    // module.exports = foo; or exports.default = foo;
+    fn assign_default_export(&mut self, span: Span, value: Expr) -> ModuleItem {
+        if self.has_named_exports {
+            self.report.borrow_mut().warn(
+                span,
+                "the default export was assigned to `exports.default` since the module has named exports as well",
+            );
+            return assign_stmt(span, exports_member(span, "default"), value);
+        }
+        assign_stmt(span, module_exports(span), value)
+    }
+
+    // export { foo, bar as baz }; as the only export of the module
+    fn is_export_object(&self, export: &NamedExport) -> bool {
+        self.export_count == 1
+            && export.src.is_none()
+            && !export.type_only
+            && export.specifiers.len() > 1
+            && export.specifiers.iter().all(|specifier| match specifier {
+                ExportSpecifier::Named(ExportNamedSpecifier { orig: ModuleExportName::Ident(..), exported, is_type_only: false, .. }) => {
+                    !matches!(exported, Some(ModuleExportName::Ident(Ident { sym, .. })) if sym == "default")
+                },
+                _ => false,
+            })
+    }
+
+    // module.exports = { foo, baz: bar };
+    fn export_object(&mut self, export: NamedExport) -> ModuleItem {
+        let props = export
+            .specifiers
+            .into_iter()
+            .filter_map(|specifier| match specifier {
+                ExportSpecifier::Named(ExportNamedSpecifier { orig: ModuleExportName::Ident(orig), exported, .. }) => {
+                    let key = match exported {
+                        Some(ModuleExportName::Ident(exported)) if exported.sym != orig.sym => PropName::Ident(exported),
+                        Some(ModuleExportName::Str(exported)) => PropName::Str(exported),
+                        _ => return Some(PropOrSpread::Prop(Box::new(Prop::Shorthand(orig)))),
+                    };
+                    let value = Box::new(Expr::Ident(orig));
+                    Some(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp { key, value }))))
+                },
+                _ => None,
+            })
+            .collect();
+        let object = Expr::Object(ObjectLit { span: export.span, props });
+        assign_stmt(export.span, module_exports(export.span), object)
+    }
+
+    fn transform_var(&mut self, span: Span, var: VarDecl) -> Vec<ModuleItem> {
+        if let [VarDeclarator { name: Pat::Ident(name), init: Some(init), .. }] = &var.decls[..] {
+            if !self.values.contains(&name.sym) && !self.reassigned.contains(&name.sym) {
+                let name = name.sym.clone();
+                return vec![self.assign_export(span, &name, *init.clone())];
+            }
+        }
+        let names: Vec<Ident> = var.decls.iter().flat_map(|decl| pat_binding_idents(&decl.name)).collect();
+        let mut items = vec![ModuleItem::Stmt(Stmt::Decl(Decl::Var(Box::new(var))))];
+        for name in names {
+            let exported = name.sym.clone();
+            items.push(self.assign_export(span, &exported, Expr::Ident(name)));
+        }
+        items
+    }
+
+    fn transform_item(&mut self, item: ModuleItem) -> Vec<ModuleItem> {
+        let decl = match item {
+            ModuleItem::ModuleDecl(decl) => decl,
+            item => return vec![item],
+        };
+        match decl {
+            ModuleDecl::ExportDecl(ExportDecl { span, decl: Decl::Var(var) }) => self.transform_var(span, *var),
+            ModuleDecl::ExportDecl(ExportDecl { span, decl: Decl::Fn(decl) }) => {
+                let ident = decl.ident.clone();
+                vec![ModuleItem::Stmt(Stmt::Decl(Decl::Fn(decl))), self.assign_export(span, &ident.sym.clone(), Expr::Ident(ident))]
+            },
+            ModuleDecl::ExportDecl(ExportDecl { span, decl: Decl::Class(decl) }) => {
+                let ident = decl.ident.clone();
+                vec![ModuleItem::Stmt(Stmt::Decl(Decl::Class(decl))), self.assign_export(span, &ident.sym.clone(), Expr::Ident(ident))]
+            },
+            ModuleDecl::ExportNamed(export) if self.is_export_object(&export) => vec![self.export_object(export)],
+            ModuleDecl::ExportNamed(NamedExport { span, specifiers, src, type_only: false, .. }) => specifiers
+                .into_iter()
+                .filter_map(|specifier| {
+                    let (exported, value) = match (specifier, &src) {
+                        (ExportSpecifier::Named(ExportNamedSpecifier { is_type_only: true, .. }), _) => return None,
+                        // export { foo as bar };
+                        (ExportSpecifier::Named(ExportNamedSpecifier { orig: ModuleExportName::Ident(orig), exported, .. }), None) => {
+                            (exported.unwrap_or_else(|| ModuleExportName::Ident(orig.clone())), Expr::Ident(orig))
+                        },
+                        // export { foo as bar } from 'foo';
+                        (ExportSpecifier::Named(ExportNamedSpecifier { orig, exported, .. }), Some(src)) => {
+                            let exported = exported.unwrap_or_else(|| orig.clone());
+                            let prop = match orig {
+                                ModuleExportName::Ident(orig) => MemberProp::Ident(orig),
+                                ModuleExportName::Str(orig) => MemberProp::Computed(ComputedPropName {
+                                    span: orig.span,
+                                    expr: Box::new(Expr::Lit(Lit::Str(orig))),
+                                }),
+                            };
+                            let value = Expr::Member(MemberExpr { span, obj: Box::new(require_call(*src.clone())), prop });
+                            (exported, value)
+                        },
+                        // export * as foo from 'foo';
+                        (ExportSpecifier::Namespace(ExportNamespaceSpecifier { name, .. }), Some(src)) => {
+                            (name, require_call(*src.clone()))
+                        },
+                        _ => return None,
+                    };
+                    let exported = match exported {
+                        ModuleExportName::Ident(exported) => exported.sym,
+                        ModuleExportName::Str(exported) => exported.value,
+                    };
+                    Some(self.assign_export(span, &exported, value))
+                })
+                .collect(),
+            // Object.assign(exports, require('foo'));
+            ModuleDecl::ExportAll(ExportAll { span, src, .. }) => {
+                let object_assign = Expr::Member(MemberExpr {
+                    span,
+                    obj: Box::new(Expr::Ident(Ident::new("Object".into(), span))),
+                    prop: MemberProp::Ident(Ident::new("assign".into(), span)),
+                });
+                let call = Expr::Call(CallExpr {
+                    span,
+                    callee: Callee::Expr(Box::new(object_assign)),
+                    args: vec![Expr::Ident(Ident::new("exports".into(), span)).into(), require_call(*src).into()],
+                    type_args: None,
+                });
+                vec![ModuleItem::Stmt(Stmt::Expr(ExprStmt { span, expr: Box::new(call) }))]
+            },
+            ModuleDecl::ExportDefaultExpr(ExportDefaultExpr { span, expr }) => vec![self.assign_default_export(span, *expr)],
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span, decl: DefaultDecl::Fn(FnExpr { ident: Some(ident), function }) }) => {
+                let decl = Decl::Fn(FnDecl { ident: ident.clone(), declare: false, function });
+                vec![ModuleItem::Stmt(Stmt::Decl(decl)), self.assign_default_export(span, Expr::Ident(ident))]
+            },
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span, decl: DefaultDecl::Class(ClassExpr { ident: Some(ident), class }) }) => {
+                let decl = Decl::Class(ClassDecl { ident: ident.clone(), declare: false, class });
+                vec![ModuleItem::Stmt(Stmt::Decl(decl)), self.assign_default_export(span, Expr::Ident(ident))]
+            },
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span, decl: DefaultDecl::Fn(function) }) => {
+                vec![self.assign_default_export(span, Expr::Fn(function))]
+            },
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { span, decl: DefaultDecl::Class(class) }) => {
+                vec![self.assign_default_export(span, Expr::Class(class))]
+            },
+            // Type declarations and type only exports are left for the TypeScript compiler
+            decl => vec![ModuleItem::ModuleDecl(decl)],
+        }
+    }
+}
+
+impl VisitMut for TransformExportToAssignmentVisitor {
+    fn visit_mut_module(&mut self, m: &mut Module) {
+        self.values = collect_value_references(m);
+        self.reassigned = collect_reassigned_names(m);
+        self.has_named_exports = m.body.iter().any(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                matches!(decl, Decl::Var(..) | Decl::Fn(..) | Decl::Class(..))
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport { specifiers, type_only: false, .. })) => {
+                specifiers.iter().any(|specifier| match specifier {
+                    ExportSpecifier::Named(ExportNamedSpecifier { exported, orig, is_type_only: false, .. }) => {
+                        !matches!(exported.as_ref().unwrap_or(orig), ModuleExportName::Ident(Ident { sym, .. }) if sym == "default")
+                    },
+                    ExportSpecifier::Named(..) => false,
+                    _ => true,
+                })
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportAll(..)) => true,
+            _ => false,
+        });
+        self.export_count = m
+            .body
+            .iter()
+            .filter(|item| match item {
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                    matches!(decl, Decl::Var(..) | Decl::Fn(..) | Decl::Class(..))
+                },
+                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport { type_only, .. })) => !type_only,
+                ModuleItem::ModuleDecl(
+                    ModuleDecl::ExportAll(..) | ModuleDecl::ExportDefaultExpr(..) | ModuleDecl::ExportDefaultDecl(..),
+                ) => true,
+                _ => false,
+            })
+            .count();
+        m.body = m.body.take().into_iter().flat_map(|item| self.transform_item(item)).collect();
+    }
+}
+
+fn already_exported(name: &str) -> String {
+    format!("`{}` is exported with `export` already, the assignment to it was left as it is", name)
+}
+
+fn var_declarator(span: Span, name: Pat, init: Expr) -> VarDeclarator {
+    VarDeclarator { span, name, init: Some(Box::new(init)), definite: false }
+}
+
+fn assign_stmt(span: Span, left: Expr, right: Expr) -> ModuleItem {
+    ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+        span,
+        expr: Box::new(Expr::Assign(AssignExpr {
+            span,
+            op: AssignOp::Assign,
+            left: PatOrExpr::Pat(Box::new(Pat::Expr(Box::new(left)))),
+            right: Box::new(right),
+        })),
+    }))
+}
+
+/**
+    Whether an assignment is the whole expression of the statement being visited, ie `exports.foo = 1;`.
+    Export assignments anywhere else, like `exports.foo = exports.bar = 1`, can't be replaced by an export.
+ */
+fn is_statement(statement: Span, e: &AssignExpr) -> bool {
+    e.span == statement
+}
+
+/**
+    How `src` should be imported. Apart from JSON files this is only known when converting a file of a project.
+ */
+fn interop<'a>(project: &'a Option<ProjectFile>, src: &Str) -> Interop<'a> {
+    // JSON files are parsed into the default export, with or without a project
+    if is_json(&src.value) {
+        return Interop::Default;
+    }
+    project
+        .as_ref()
+        .map_or(Interop::Namespace, |project| project.interop(&src.value))
+}
+
+/**
+    CommonJS packages whose module object is always used as a default import, ie `import React from 'react'`,
+    even when the package can't be looked up. Destructured requires of them still become named imports.
+ */
+const DEFAULT_IMPORT_PACKAGES: &[&str] = &["react", "react-dom"];
+
+/**
+    Whether the module object of `const foo = require(src)` should be default imported.
+ */
+fn is_default_import(project: &Option<ProjectFile>, src: &Str) -> bool {
+    DEFAULT_IMPORT_PACKAGES.contains(&&*src.value) || interop(project, src) == Interop::Default
+}
@@ -0,0 +1,104 @@
+//! Runs the `cjs-to-esm` binary over files in a temporary directory and checks what it prints and writes.
+//!
+//! Each test gets a directory of its own under cargo's temporary directory for integration tests,
+//! which is emptied before the test writes its files into it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn setup(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    for (path, src) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+    }
+    dir
+}
+
+fn run(cwd: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_cjs-to-esm")).current_dir(cwd).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn prints_to_stdout() {
+    let dir = setup("prints_to_stdout", &[("index.js", "const path = require('path');\nmodule.exports = path;\n")]);
+    let output = run(&dir, &["index.js"]);
+    assert_eq!(stdout(&output), "import * as path from 'path';\nexport default path;\n\n");
+}
+
+#[test]
+fn writes_with_extension() {
+    let dir = setup("writes_with_extension", &[("src/a.js", "module.exports = 1;\n")]);
+    run(&dir, &["--write", "--extension", ".mjs", "src/*.js"]);
+    assert_eq!(fs::read_to_string(dir.join("src/a.mjs")).unwrap(), "export default 1;\n");
+    assert_eq!(fs::read_to_string(dir.join("src/a.js")).unwrap(), "module.exports = 1;\n");
+}
+
+#[test]
+fn out_dir_keeps_parent_paths_apart() {
+    let dir = setup(
+        "out_dir_keeps_parent_paths_apart",
+        &[("app/lib/index.js", "module.exports = 'app';\n"), ("lib/index.js", "module.exports = 'lib';\n")],
+    );
+    // `../lib/index.js` used to end up in `out/lib/index.js` too, overwriting the other one
+    run(&dir.join("app"), &["--out-dir", "../out", "lib/index.js", "../lib/index.js"]);
+    assert_eq!(fs::read_to_string(dir.join("out/app/lib/index.js")).unwrap(), "export default 'app';\n");
+    assert_eq!(fs::read_to_string(dir.join("out/lib/index.js")).unwrap(), "export default 'lib';\n");
+}
+
+#[test]
+fn out_dir_keeps_relative_paths() {
+    let dir = setup("out_dir_keeps_relative_paths", &[("src/nested/a.js", "module.exports = 1;\n")]);
+    run(&dir, &["--out-dir", "out", "src/**/*.js"]);
+    assert_eq!(fs::read_to_string(dir.join("out/src/nested/a.js")).unwrap(), "export default 1;\n");
+}
+
+#[test]
+fn no_resolve_overrides_target() {
+    let files = [("index.js", "const a = require('./a');\nmodule.exports = a;\n"), ("a.js", "module.exports = 1;\n")];
+    let dir = setup("no_resolve_overrides_target", &files);
+    let resolved = run(&dir, &["--target", "node18", "index.js"]);
+    assert!(stdout(&resolved).contains("from './a.js'"), "{}", stdout(&resolved));
+    let unresolved = run(&dir, &["--target", "node18", "--no-resolve", "index.js"]);
+    assert!(stdout(&unresolved).contains("from './a'"), "{}", stdout(&unresolved));
+}
+
+#[test]
+fn no_glob_imports_overrides_target() {
+    let src = "function page(name) {\n  return require(`./pages/${name}.js`);\n}\nmodule.exports = page;\n";
+    let dir = setup("no_glob_imports_overrides_target", &[("index.js", src)]);
+    let globbed = run(&dir, &["--target", "vite", "index.js"]);
+    assert!(stdout(&globbed).contains("import.meta.glob"), "{}", stdout(&globbed));
+    let required = run(&dir, &["--target", "vite", "--no-glob-imports", "index.js"]);
+    assert!(stdout(&required).contains("require(`./pages/${name}.js`)"), "{}", stdout(&required));
+}
+
+#[test]
+fn no_json_attributes_overrides_target() {
+    let src = "const a = require('./a.json');\nmodule.exports = a;\n";
+    let dir = setup("no_json_attributes_overrides_target", &[("index.js", src)]);
+    let attributed = run(&dir, &["--target", "node22", "index.js"]);
+    assert!(stdout(&attributed).contains("from './a.json' with {"), "{}", stdout(&attributed));
+    let plain = run(&dir, &["--target", "node22", "--no-json-attributes", "index.js"]);
+    assert!(stdout(&plain).contains("from './a.json';"), "{}", stdout(&plain));
+}
+
+#[test]
+fn report_goes_to_stderr() {
+    let dir = setup("report_goes_to_stderr", &[("index.js", "module.exports = require.main;\n")]);
+    let output = run(&dir, &["--report", "index.js"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`require.main` has no ESM counterpart"), "{}", stderr);
+    assert!(stderr.contains("\"defaultExports\": 1"), "{}", stderr);
+}
@@ -0,0 +1,4 @@
+const resolver = {
+    resolve: (file)=>file
+};
+module.exports = resolver;
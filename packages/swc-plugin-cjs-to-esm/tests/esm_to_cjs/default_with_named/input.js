@@ -0,0 +1,4 @@
+export const version = '1.0.0';
+export default function create() {
+    return {};
+}
@@ -0,0 +1,5 @@
+exports.version = '1.0.0';
+function create() {
+    return {};
+}
+exports.default = create;
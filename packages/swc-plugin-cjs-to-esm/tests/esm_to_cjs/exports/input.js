@@ -0,0 +1,14 @@
+import { helper } from './helper';
+export const foo = () => {};
+export const bar = 123;
+export let count = 0;
+export function increment() {
+    count++;
+    return helper(bar);
+}
+export class Counter {}
+const baz = 'baz';
+export { baz, baz as qux };
+export { parse, format as stringify } from './format';
+export * as utils from './utils';
+export * from './constants';
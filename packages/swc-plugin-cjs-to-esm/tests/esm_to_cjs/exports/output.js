@@ -0,0 +1,21 @@
+const { helper  } = require('./helper');
+exports.foo = ()=>{};
+const bar = 123;
+exports.bar = bar;
+let count = 0;
+exports.count = count;
+function increment() {
+    count++;
+    return helper(bar);
+}
+exports.increment = increment;
+class Counter {
+}
+exports.Counter = Counter;
+const baz = 'baz';
+exports.baz = baz;
+exports.qux = baz;
+exports.parse = require('./format').parse;
+exports.stringify = require('./format').format;
+exports.utils = require('./utils');
+Object.assign(exports, require('./constants'));
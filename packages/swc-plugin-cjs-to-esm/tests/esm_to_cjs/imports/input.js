@@ -0,0 +1,7 @@
+import 'polyfill';
+import * as fs from 'fs';
+import path from 'path';
+import { readFile, writeFile as write } from 'fs/promises';
+import React, { useState } from 'react';
+
+fs.readFileSync(path.join(__dirname, 'foo'));
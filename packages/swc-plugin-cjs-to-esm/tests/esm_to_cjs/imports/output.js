@@ -0,0 +1,7 @@
+require('polyfill');
+const fs = require('fs');
+const path = require('path');
+const { readFile , writeFile: write  } = require('fs/promises');
+const React = require('react');
+const { useState  } = require('react');
+fs.readFileSync(path.join(__dirname, 'foo'));
@@ -0,0 +1,7 @@
+import { readFile } from "fs";
+import { writeFile } from "fs";
+
+const module = { exports: {} };
+module.exports.read = readFile;
+
+export { writeFile, module };
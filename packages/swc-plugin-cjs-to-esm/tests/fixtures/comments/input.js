@@ -0,0 +1,22 @@
+/*!
+ * my-lib
+ * Licensed under MIT
+ */
+const path = require('path'); // node builtin
+// Load polyfills first
+require('./polyfills'); // side effects only
+const { join } = require('path');
+
+function helper() {}
+
+/**
+ * Does foo things.
+ * @param {string} a
+ */
+exports.foo = function (a) {
+  return join(a, helper());
+};
+
+// Re-exported for tests
+module.exports.helper = helper; // keep in sync
+exports.version = '1.0.0'; // bumped by release script
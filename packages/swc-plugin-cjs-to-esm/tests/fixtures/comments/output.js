@@ -0,0 +1,22 @@
+/*!
+ * my-lib
+ * Licensed under MIT
+ */
+import * as path from 'path'; // node builtin
+// Load polyfills first
+import './polyfills'; // side effects only
+import { join } from 'path';
+
+function helper() {}
+
+// Re-exported for tests
+export { helper }; // keep in sync
+
+/**
+ * Does foo things.
+ * @param {string} a
+ */
+export const foo = function (a) {
+  return join(a, helper());
+};
+export const version = '1.0.0'; // bumped by release script
@@ -0,0 +1,4 @@
+const fs = require('fs');
+const root = __dirname;
+
+module.exports = { root, __filename, read: (file) => fs.readFileSync(`${__dirname}/${file}`) };
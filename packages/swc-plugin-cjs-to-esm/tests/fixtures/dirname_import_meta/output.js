@@ -0,0 +1,5 @@
+import * as fs from 'fs';
+const root = import.meta.dirname;
+const ___filename$1 = import.meta.filename;
+const _read$2 = (file)=>fs.readFileSync(`${import.meta.dirname}/${file}`);
+export { root, ___filename$1 as __filename, _read$2 as read };
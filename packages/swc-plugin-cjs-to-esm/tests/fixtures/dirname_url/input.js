@@ -0,0 +1,8 @@
+const path = require('path');
+const templates = path.join(__dirname, 'templates');
+
+function render(name, __dirname) {
+  return path.join(__dirname, name);
+}
+
+module.exports = { templates, render, file: __filename };
@@ -0,0 +1,10 @@
+import { fileURLToPath } from 'url';
+import { dirname } from 'path';
+import * as path from 'path';
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = dirname(__filename);
+const templates = path.join(__dirname, 'templates');
+function render(name, __dirname) {
+    return path.join(__dirname, name);
+}
+export { templates, render, __filename as file };
@@ -0,0 +1,4 @@
+/* cjs-to-esm-disable */
+const { load } = require('./loader');
+
+module.exports = load(require.resolve('./config.json'));
@@ -0,0 +1,13 @@
+for (const value of values) {
+  console.log(value);
+}
+
+function load() {
+  var config = {};
+  return config;
+}
+
+module.exports = function main() {};
+module.exports.value = value;
+module.exports.config = config;
+module.exports.main = main;
@@ -0,0 +1,14 @@
+for (const value1 of values){
+    console.log(value1);
+}
+function load() {
+    var config1 = {};
+    return config1;
+}
+const _value$1 = value;
+const _config$2 = config;
+const _main$3 = main;
+export { _value$1 as value };
+export { _config$2 as config };
+export { _main$3 as main };
+export default function main1() {}
@@ -0,0 +1,6 @@
+/* Copyright (c) the authors, MIT license */
+// cjs-to-esm-ignore-next-line native addons can't be imported
+const addon = require('./build/Release/addon.node');
+const path = require('path');
+
+module.exports = { addon, path };
@@ -0,0 +1,4 @@
+/* Copyright (c) the authors, MIT license */ import * as path from 'path';
+// cjs-to-esm-ignore-next-line native addons can't be imported
+const addon = require('./build/Release/addon.node');
+export { addon, path };
@@ -0,0 +1,16 @@
+const path = require('path');
+// cjs-to-esm-ignore-next-line native addons can't be imported
+const addon = require('./build/Release/addon.node');
+
+function load(name) {
+  // cjs-to-esm-ignore-next-line
+  return require.resolve(path.join('./plugins', name));
+}
+
+const config = {
+  // cjs-to-esm-ignore-next-line
+  fallback: require('./fallback'),
+  paths: require.resolve('./paths'),
+};
+
+module.exports = { addon, load, config };
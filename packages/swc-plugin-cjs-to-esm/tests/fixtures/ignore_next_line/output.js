@@ -0,0 +1,14 @@
+import { fileURLToPath } from 'url';
+import * as path from 'path';
+// cjs-to-esm-ignore-next-line native addons can't be imported
+const addon = require('./build/Release/addon.node');
+function load(name) {
+    // cjs-to-esm-ignore-next-line
+    return require.resolve(path.join('./plugins', name));
+}
+const config = {
+    // cjs-to-esm-ignore-next-line
+    fallback: require('./fallback'),
+    paths: fileURLToPath(import.meta.resolve('./paths'))
+};
+export { addon, load, config };
@@ -0,0 +1,18 @@
+const React = require('react');
+const ReactDOM = require('react-dom');
+const { useState } = require('react');
+const Button = require('./Button');
+
+function Counter({ label }) {
+    const [count, setCount] = useState(0);
+    return (
+        <div className="counter">
+            <Button onClick={() => setCount(count + 1)}>{label}</Button>
+            <span>{count}</span>
+        </div>
+    );
+}
+
+ReactDOM.render(<Counter label="+1" />, document.getElementById('root'));
+
+module.exports = Counter;
@@ -0,0 +1,15 @@
+import React, { useState } from 'react';
+import ReactDOM from 'react-dom';
+import * as Button from './Button';
+function Counter({ label  }) {
+    const [count, setCount] = useState(0);
+    return <div className="counter">
+
+            <Button onClick={()=>setCount(count + 1)}>{label}</Button>
+
+            <span >{count}</span>
+
+        </div>;
+}
+ReactDOM.render(<Counter label="+1"/>, document.getElementById('root'));
+export default Counter;
@@ -0,0 +1,6 @@
+const count = 1;
+exports.count = count + 1;
+exports.timeout = setTimeout;
+module.exports.log = log;
+exports.twice = 1;
+if (process.env.DEBUG) exports.twice = 2;
@@ -0,0 +1,8 @@
+const count = 1;
+exports.twice = 1;
+if (process.env.DEBUG) exports.twice = 2;
+const _count$1 = count + 1;
+const _log$2 = log;
+export { _count$1 as count };
+export const timeout = setTimeout;
+export { _log$2 as log };
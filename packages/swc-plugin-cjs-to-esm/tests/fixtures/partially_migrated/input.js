@@ -0,0 +1,19 @@
+import { readFile } from 'fs';
+import * as path from 'path';
+import defaults from './defaults';
+const { writeFile, stat: fileStat } = require('fs');
+const { join } = require('path');
+const helpers = require('./defaults');
+const { format } = require('./format');
+
+export function read(file) {
+  return readFile(join(path.sep, file));
+}
+
+export const version = '1.0.0';
+
+function write(file, data) {
+  return writeFile(file, format(data, defaults));
+}
+
+module.exports = { read, write, version, fileStat, helpers };
@@ -0,0 +1,13 @@
+import { readFile, writeFile, stat as fileStat } from 'fs';
+import * as path from 'path';
+import defaults, * as helpers from './defaults';
+import { join } from 'path';
+import { format } from './format';
+export function read(file) {
+    return readFile(join(path.sep, file));
+}
+export const version = '1.0.0';
+function write(file, data) {
+    return writeFile(file, format(data, defaults));
+}
+export { write, fileStat, helpers };
@@ -0,0 +1,6 @@
+import "./polyfills";
+import * as foo from "foo";
+import { bar } from "it's";
+import * as baz from "say \"hi\"";
+import "./back\\slash";
+export { foo, bar, baz };
@@ -0,0 +1,7 @@
+import "./polyfills";
+const foo = require("foo");
+const { bar } = require("it's");
+const baz = require('say "hi"');
+require('./back\\slash');
+
+module.exports = { foo, bar, baz };
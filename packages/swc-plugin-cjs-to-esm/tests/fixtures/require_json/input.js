@@ -0,0 +1,5 @@
+const config = require('./config.json');
+const { version, name: pkgName } = require('../package.json');
+const greeting = require('./locales/en.json').greeting;
+
+console.log(config.port, version, pkgName, greeting);
@@ -0,0 +1,6 @@
+import config from './config.json';
+import _package$1 from '../package.json';
+import _mod$1 from './locales/en.json';
+const { version , name: pkgName  } = _package$1;
+const greeting = _mod$1.greeting;
+console.log(config.port, version, pkgName, greeting);
@@ -0,0 +1,12 @@
+const { fileURLToPath } = require('url');
+const run = require('./run');
+
+if (require.main === module) {
+  run(require.resolve('./config.json'));
+}
+
+if (module !== require.main) {
+  console.log('imported from', fileURLToPath(__filename));
+}
+
+module.exports = run;
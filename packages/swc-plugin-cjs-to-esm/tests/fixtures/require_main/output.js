@@ -0,0 +1,10 @@
+import { fileURLToPath, pathToFileURL } from 'url';
+import * as run from './run';
+const __filename = fileURLToPath(import.meta.url);
+if (import.meta.url === pathToFileURL(process.argv[1]).href) {
+    run(fileURLToPath(import.meta.resolve('./config.json')));
+}
+if (import.meta.url !== pathToFileURL(process.argv[1]).href) {
+    console.log('imported from', fileURLToPath(__filename));
+}
+export default run;
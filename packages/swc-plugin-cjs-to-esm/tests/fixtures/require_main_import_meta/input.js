@@ -0,0 +1,9 @@
+const run = require('./run');
+
+if (module === require.main) {
+  run();
+}
+
+const imported = require.main != module;
+
+module.exports = { run, imported };
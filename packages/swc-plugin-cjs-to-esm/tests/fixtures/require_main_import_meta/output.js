@@ -0,0 +1,6 @@
+import * as run from './run';
+if (import.meta.main) {
+    run();
+}
+const imported = !import.meta.main;
+export { run, imported };
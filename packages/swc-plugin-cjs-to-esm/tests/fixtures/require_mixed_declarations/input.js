@@ -0,0 +1,7 @@
+// Split into one statement per declarator
+const fs = require('fs'), path = require('path');
+const { join } = require('path'), sep = '/', root = join(sep, 'root');
+let cache = {}, config = require('./config').value;
+
+// `load()` would run after `./plugins` once the require is hoisted, so these stay together
+const loaded = load(), plugins = require('./plugins');
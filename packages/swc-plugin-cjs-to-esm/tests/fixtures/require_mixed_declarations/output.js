@@ -0,0 +1,11 @@
+// Split into one statement per declarator
+import * as fs from 'fs';
+import * as path from 'path';
+import { join } from 'path';
+import * as _mod$1 from './config';
+const sep = '/';
+const root = join(sep, 'root');
+let cache = {};
+let config = _mod$1.value;
+// `load()` would run after `./plugins` once the require is hoisted, so these stay together
+const loaded = load(), plugins = require('./plugins');
@@ -0,0 +1,4 @@
+const { a: { b } } = require('x');
+let { c, d: { e: f }, g: [h] } = require('y');
+var { i, ...rest } = require('@scope/z-pkg');
+const { j = 1 } = require('./w.js');
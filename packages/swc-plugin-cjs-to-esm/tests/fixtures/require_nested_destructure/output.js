@@ -0,0 +1,8 @@
+import { a as _a$1 } from 'x';
+import { c, d as _d$2, g as _g$3 } from 'y';
+import * as _zPkg$4 from '@scope/z-pkg';
+import * as _w$5 from './w.js';
+const { b } = _a$1;
+let { e: f } = _d$2, [h] = _g$3;
+var { i, ...rest } = _zPkg$4;
+const { j = 1 } = _w$5;
@@ -0,0 +1,5 @@
+require('./setup');
+const a = require('./a');
+const { b, double } = require('./b');
+
+module.exports = { a, b: double(b) };
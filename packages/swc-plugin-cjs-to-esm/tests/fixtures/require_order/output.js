@@ -0,0 +1,5 @@
+import './setup';
+import * as a from './a';
+import { b, double } from './b';
+const _b$1 = double(b);
+export { a, _b$1 as b };
@@ -0,0 +1,11 @@
+let foo = require('foo');
+var { bar, baz: qux, keep } = require('bar');
+let { nested: { value } } = require('nested');
+const fixed = require('fixed');
+
+if (process.env.MOCK) {
+  foo = require('./mock-foo');
+  ({ bar } = {});
+  qux++;
+  value = null;
+}
@@ -0,0 +1,14 @@
+import * as _foo$1 from 'foo';
+import { bar as _bar$1, baz as _baz$2, keep } from 'bar';
+import { nested as _nested$3 } from 'nested';
+import * as fixed from 'fixed';
+let foo = _foo$1;
+var bar = _bar$1, qux = _baz$2;
+let { value } = _nested$3;
+
+if (process.env.MOCK) {
+  foo = require('./mock-foo');
+  ({ bar } = {});
+  qux++;
+  value = null;
+}
@@ -0,0 +1,5 @@
+const shared = require('./shared');
+const again = require('./shared');
+const value = require('./shared').value;
+
+module.exports = { again, value, run: () => shared.run(again) };
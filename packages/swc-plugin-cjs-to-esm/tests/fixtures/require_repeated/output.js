@@ -0,0 +1,4 @@
+import * as shared from './shared';
+const value = shared.value;
+const _run$1 = ()=>shared.run(shared);
+export { shared as again, value, _run$1 as run };
@@ -0,0 +1,8 @@
+const shared = require('./shared');
+const again = require('./shared');
+
+function run(shared) {
+  return again.run(shared);
+}
+
+module.exports = { shared, run };
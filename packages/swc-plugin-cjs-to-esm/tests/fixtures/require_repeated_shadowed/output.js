@@ -0,0 +1,6 @@
+import * as shared from './shared';
+import * as again from './shared';
+function run(shared) {
+    return again.run(shared);
+}
+export { shared, run };
@@ -0,0 +1,10 @@
+const fs = require('fs');
+
+const pkg = require.resolve('lodash/package.json');
+const template = fs.readFileSync(require.resolve('./template.html'), 'utf8');
+
+function locate(name) {
+  return require.resolve(name);
+}
+
+module.exports = { pkg, template, locate };
@@ -0,0 +1,8 @@
+import { fileURLToPath } from 'url';
+import * as fs from 'fs';
+const pkg = fileURLToPath(import.meta.resolve('lodash/package.json'));
+const template = fs.readFileSync(fileURLToPath(import.meta.resolve('./template.html')), 'utf8');
+function locate(name) {
+    return fileURLToPath(import.meta.resolve(name));
+}
+export { pkg, template, locate };
@@ -0,0 +1,8 @@
+const { fileURLToPath } = require('url');
+
+const here = fileURLToPath(__filename);
+const template = require.resolve('./template.html');
+const pkg = require.resolve('lodash/package.json');
+const local = require.resolve('./lib', { paths: [process.cwd()] });
+
+module.exports = { here, template, pkg, local };
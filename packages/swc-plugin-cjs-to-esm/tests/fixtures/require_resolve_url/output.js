@@ -0,0 +1,11 @@
+import { fileURLToPath } from 'url';
+const __filename = fileURLToPath(import.meta.url);
+const here = fileURLToPath(__filename);
+const template = fileURLToPath(new URL('./template.html', import.meta.url));
+const pkg = fileURLToPath(import.meta.resolve('lodash/package.json'));
+const local = require.resolve('./lib', {
+    paths: [
+        process.cwd()
+    ]
+});
+export { here, template, pkg, local };
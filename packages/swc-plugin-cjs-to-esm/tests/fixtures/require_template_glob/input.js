@@ -0,0 +1,11 @@
+const path = require('path');
+
+function messages(lang) {
+  return require(`./locales/${lang}.json`);
+}
+
+const icon = (theme, name) => require(`../assets/${theme}/${name}.svg`);
+const page = require(`./pages/${path.basename(process.argv[2])}`);
+const plugin = require(`eslint-plugin-${process.env.PLUGIN}`);
+
+module.exports = { messages, icon, page, plugin };
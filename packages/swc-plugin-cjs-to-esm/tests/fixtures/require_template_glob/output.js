@@ -0,0 +1,12 @@
+import * as path from 'path';
+function messages(lang) {
+    return import.meta.glob('./locales/*.json', {
+        eager: true
+    })[`./locales/${lang}.json`];
+}
+const icon = (theme, name)=>import.meta.glob('../assets/*/*.svg', {
+        eager: true
+    })[`../assets/${theme}/${name}.svg`];
+const page = require(`./pages/${path.basename(process.argv[2])}`);
+const plugin = require(`eslint-plugin-${process.env.PLUGIN}`);
+export { messages, icon, page, plugin };
@@ -0,0 +1,12 @@
+const run = require('./run');
+
+const isMain = (require, module) => require.main === module;
+const locate = (require) => require.resolve('./config');
+
+if (require.main === module) {
+  run(require.resolve('./config'));
+}
+
+['./a', './b'].map(module => run(module));
+
+module.exports = { isMain, locate };
@@ -0,0 +1,12 @@
+import { pathToFileURL, fileURLToPath } from 'url';
+import * as run from './run';
+const isMain = (require, module)=>require.main === module;
+const locate = (require)=>require.resolve('./config');
+if (import.meta.url === pathToFileURL(process.argv[1]).href) {
+    run(fileURLToPath(import.meta.resolve('./config')));
+}
+[
+    './a',
+    './b'
+].map((module)=>run(module));
+export { isMain, locate };
@@ -0,0 +1,4 @@
+const path = require('path');
+const load = (require) => require('./x');
+
+module.exports = { path, load };
@@ -0,0 +1,3 @@
+import * as path from 'path';
+const load = (require)=>require('./x');
+export { path, load };
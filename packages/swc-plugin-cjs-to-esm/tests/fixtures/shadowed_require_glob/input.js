@@ -0,0 +1,7 @@
+function messages(lang) {
+  return require(`./locales/${lang}.json`);
+}
+
+const plugin = (require, name) => require(`./plugins/${name}.js`);
+
+module.exports = { messages, plugin };
@@ -0,0 +1,7 @@
+function messages(lang) {
+    return import.meta.glob('./locales/*.json', {
+        eager: true
+    })[`./locales/${lang}.json`];
+}
+const plugin = (require, name)=>require(`./plugins/${name}.js`);
+export { messages, plugin };
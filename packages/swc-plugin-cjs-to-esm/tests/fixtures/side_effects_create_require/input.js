@@ -0,0 +1,16 @@
+const path = require('path');
+const { version } = require('./package.json');
+
+// Has to be set before ./config reads it
+process.env.APP_VERSION = version;
+
+const config = require('./config');
+const { debug } = require('./debug').options;
+require('./polyfills');
+
+function load(name) {
+  const plugin = require('./plugins/' + name);
+  return require('./plugin-loader').load(plugin);
+}
+
+module.exports = { config, debug, load };
@@ -0,0 +1,16 @@
+import { createRequire } from 'module';
+import * as path from 'path';
+import _package$1 from './package.json';
+import * as _mod$1 from './plugin-loader';
+const _require$1 = createRequire(import.meta.url);
+const { version  } = _package$1;
+// Has to be set before ./config reads it
+process.env.APP_VERSION = version;
+const config = _require$1('./config');
+const { debug  } = _require$1('./debug').options;
+_require$1('./polyfills');
+function load(name) {
+    const plugin = require('./plugins/' + name);
+    return _mod$1.load(plugin);
+}
+export { config, debug, load };
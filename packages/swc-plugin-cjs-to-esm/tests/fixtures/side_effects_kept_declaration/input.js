@@ -0,0 +1,4 @@
+const start = Date.now(), config = require('./config'), { debug } = require('./debug').options;
+const path = require('path');
+
+module.exports = { start, config, debug, path };
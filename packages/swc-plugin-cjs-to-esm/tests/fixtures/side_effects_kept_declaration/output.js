@@ -0,0 +1,5 @@
+import { createRequire } from 'module';
+const _require$1 = createRequire(import.meta.url);
+const start = Date.now(), config = _require$1('./config'), { debug  } = _require$1('./debug').options;
+const path = _require$1('path');
+export { start, config, debug, path };
@@ -0,0 +1,7 @@
+process.env.NODE_ENV = 'production';
+const config = require('./config');
+const schema = require.resolve('./schema.json');
+
+if (require.main === module) {
+  config.load(schema);
+}
@@ -0,0 +1,7 @@
+import { pathToFileURL, fileURLToPath } from 'url';
+import * as config from './config';
+process.env.NODE_ENV = 'production';
+const schema = fileURLToPath(new URL('./schema.json', import.meta.url));
+if (import.meta.url === pathToFileURL(process.argv[1]).href) {
+    config.load(schema);
+}
@@ -0,0 +1,9 @@
+const render = require('./render');
+
+function page(name) {
+  return require(`./pages/${name}.js`);
+}
+
+if (require.main === module) {
+  render(page('home'), require.resolve('./layout.html'));
+}
@@ -0,0 +1,9 @@
+import * as render from './render';
+function page(name) {
+    return import.meta.glob('./pages/*.js', {
+        eager: true
+    })[`./pages/${name}.js`];
+}
+if (import.meta.main) {
+    render(page('home'), require.resolve('./layout.html'));
+}
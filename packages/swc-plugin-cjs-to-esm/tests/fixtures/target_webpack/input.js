@@ -0,0 +1,5 @@
+const worker = require.resolve('./worker');
+
+if (require.main === module) {
+  start(worker);
+}
@@ -0,0 +1,4 @@
+const worker = require.resolve('./worker');
+if (import.meta.main) {
+    start(worker);
+}
@@ -0,0 +1,11 @@
+import path = require('path');
+
+interface Resolver {
+    resolve(file: string): string;
+}
+
+const resolver: Resolver = {
+    resolve: (file: string) => path.resolve(file),
+};
+
+export = resolver;
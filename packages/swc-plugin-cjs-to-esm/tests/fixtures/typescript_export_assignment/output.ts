@@ -0,0 +1,8 @@
+import * as path from 'path';
+interface Resolver {
+    resolve(file: string): string;
+}
+const resolver: Resolver = {
+    resolve: (file: string)=>path.resolve(file)
+};
+export default resolver;
@@ -0,0 +1,11 @@
+import fs = require('fs');
+import type Config = require('./config');
+import Options = require('./options');
+export import utils = require('./utils');
+import Inner = Outer.Inner;
+
+const { join } = require('path');
+
+export function read(file: string, options?: Options.ReadOptions): Config {
+    return JSON.parse(fs.readFileSync(join(utils.root, file), 'utf8')) as Config;
+}
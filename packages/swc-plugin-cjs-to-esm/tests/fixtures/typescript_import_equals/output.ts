@@ -0,0 +1,10 @@
+import * as fs from 'fs';
+import * as Config from './config';
+import * as Options from './options';
+import * as utils from './utils';
+import { join } from 'path';
+export { utils };
+import Inner = Outer.Inner
+export function read(file: string, options?: Options.ReadOptions): Config {
+    return JSON.parse(fs.readFileSync(join(utils.root, file), 'utf8')) as Config;
+}
@@ -0,0 +1,139 @@
+//! Runs generated CommonJS-ish programs through the transform. Whatever the input, it must not panic and the
+//! output has to parse as a module again, checked with both swc and boa.
+//!
+//! Programs are made of statements picked from the shapes below, with names and specifiers picked from small
+//! pools so that statements end up referring to each other, ie a required binding that gets reassigned or exported.
+//! Each program is converted with a random pick of the options that change what the output looks like.
+
+use proptest::{prelude::*, sample::select, test_runner::FileFailurePersistence};
+use boa_engine::{Context, Module, Source};
+use swc_plugin_cjs_to_esm::{
+    config::{CjsToEsmConfig, MainModule, QuotesStyle, RequireResolve, SideEffects},
+    transform_source, Options, Severity,
+};
+
+const NAMES: &[&str] = &["foo", "bar", "baz", "value", "_foo$1", "$", "React", "path", "mod"];
+
+const SPECIFIERS: &[&str] = &["foo", "./foo", "../lib/bar", "@scope/pkg/sub", "node:fs", "./data.json", "with-dash", "./it's"];
+
+const KINDS: &[&str] = &["const", "let", "var"];
+
+fn statement() -> impl Strategy<Value = String> {
+    let names = || select(NAMES);
+    (0..STATEMENTS, names(), names(), names(), select(SPECIFIERS), select(KINDS))
+        .prop_map(|(shape, a, b, c, s, k)| render(shape, a, b, c, &format!("{:?}", s), k))
+}
+
+const STATEMENTS: usize = 43;
+
+fn render(shape: usize, a: &str, b: &str, c: &str, s: &str, k: &str) -> String {
+    match shape {
+        // requires
+        0 => format!("{k} {a} = require({s});"),
+        1 => format!("{k} {{ {a} }} = require({s});"),
+        2 => format!("{k} {{ {a}: {b}, {c} }} = require({s});"),
+        3 => format!("{k} {{ {a}: {{ {b} }} }} = require({s});"),
+        4 => format!("{k} {{ {a} = 1, ...{b} }} = require({s});"),
+        5 => format!("{k} [{a}] = require({s});"),
+        6 => format!("{k} {{ 'quoted-key': {a} }} = require({s});"),
+        7 => format!("require({s});"),
+        8 => format!("{k} {a} = require({s}).{b};"),
+        9 => format!("require({s}).{a}({b});"),
+        10 => format!("{k} {a} = require({s})[{b}];"),
+        11 => format!("{k} {a} = require({s})({b});"),
+        12 => format!("{k} {a} = require(`{}`);", &s[1..s.len() - 1]),
+        13 => format!("{k} {a} = require({b});"),
+        14 => format!("{k} {a} = require(1);"),
+        15 => format!("{k} {a} = require();"),
+        16 => format!("{k} {a} = require({s}), {b} = {c};"),
+        17 => format!("{k} {a} = {b}, {c} = require({s});"),
+        18 => format!("function {a}() {{ return require({s}); }}"),
+        19 => format!("if ({a}) {{ {b} = require({s}); }}"),
+        20 => format!("{a} = require({s});"),
+        // exports
+        21 => format!("module.exports = {a};"),
+        22 => format!("module.exports = {{ {a}, {b}: {c}, {s}: 1, ...{c} }};"),
+        23 => format!("module.exports = function {a}() {{}};"),
+        24 => "module.exports = class {};".to_owned(),
+        25 => format!("module.exports.{a} = {b};"),
+        26 => format!("exports.{a} = () => {a};"),
+        27 => format!("exports[{s}] = {a};"),
+        28 => format!("exports.{a} = exports.{b} = 1;"),
+        29 => format!("Object.defineProperty(exports, '{a}', {{ value: {b} }});"),
+        30 => format!("module.exports = {{ {a}() {{ return this; }}, get {b}() {{ return 1; }} }};"),
+        // everyday code around them
+        31 => format!("{a}++;"),
+        32 => format!("{a} = {b};"),
+        33 => format!("console.log({a}, module, exports, require);"),
+        34 => format!("class {a} {{ {b} = 1; static {c}; }}"),
+        35 => format!("for ({a} of {b}) {{ {c}({a}); }}"),
+        36 => format!("{k} {a};"),
+        37 => format!("{k} {a}, {b} = require({s});"),
+        38 => format!("for ({k} {a} of {b}) {{ {c}({a}); }}"),
+        39 => format!("{k} {a} = require.resolve({s});"),
+        40 => format!("if (require.main === module) {{ {a}(require.cache, module.parent); }}"),
+        41 => format!("{k} {a} = require(`./{b}/${{{c}}}.json`);"),
+        _ => format!("for ({k} {a} in {b}) {{ let {c}; }}"),
+    }
+}
+
+fn program() -> impl Strategy<Value = String> {
+    prop::collection::vec(statement(), 1..12).prop_map(|statements| statements.join("\n"))
+}
+
+fn options() -> impl Strategy<Value = Options> {
+    let side_effects = select(&[SideEffects::Warn, SideEffects::CreateRequire][..]);
+    let require_resolve = select(&[RequireResolve::ImportMetaResolve, RequireResolve::Url, RequireResolve::Warn][..]);
+    let main_module = select(&[MainModule::ProcessArgv, MainModule::ImportMetaMain][..]);
+    let quotes_style = select(&[QuotesStyle::Preserve, QuotesStyle::Single, QuotesStyle::Double][..]);
+    (side_effects, require_resolve, main_module, any::<bool>(), quotes_style).prop_map(
+        |(side_effects, require_resolve, main_module, glob_imports, quotes_style)| Options {
+            config: CjsToEsmConfig {
+                quotes_style,
+                side_effects: Some(side_effects),
+                require_resolve: Some(require_resolve),
+                main_module: Some(main_module),
+                glob_imports: Some(glob_imports),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+}
+
+proptest! {
+    // Failing programs are kept in `generated_programs.regressions` and tried first on the next run
+    #![proptest_config(ProptestConfig {
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("regressions"))),
+        // Programs that redeclare a binding are skipped, which happens a lot with the small pool of names
+        max_global_rejects: 100_000,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn converts_generated_programs(src in program(), options in options()) {
+        let output = match transform_source(&src, "input.js", &options) {
+            Ok(output) => output,
+            // Shapes can combine into something that isn't valid to begin with
+            Err(_) => return Ok(()),
+        };
+        prop_assume!(!output.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error));
+        // swc doesn't check for early errors like redeclared bindings, boa does. Programs are checked as modules,
+        // since something like `var foo; function foo() {}` is fine in a script but can't be made into a module
+        let mut context = Context::default();
+        prop_assume!(Module::parse(Source::from_bytes(&src), None, &mut context).is_ok());
+
+        let reparsed = transform_source(&output.code, "output.js", &Options::default());
+        prop_assert!(reparsed.is_ok(), "output does not parse:\n{}\n\nfrom:\n{}", output.code, src);
+        let errors: Vec<String> = reparsed
+            .unwrap()
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect();
+        prop_assert!(errors.is_empty(), "{}\n\nin:\n{}\n\nfrom:\n{}", errors.join("\n"), output.code, src);
+        let module = Module::parse(Source::from_bytes(&output.code), None, &mut context);
+        prop_assert!(module.is_ok(), "{}\n\nin:\n{}\n\nfrom:\n{}", module.unwrap_err(), output.code, src);
+    }
+}
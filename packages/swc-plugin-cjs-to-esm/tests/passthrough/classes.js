@@ -0,0 +1,33 @@
+class Counter {
+  count = 0;
+  static instances;
+  #step = 1;
+  label;
+
+  static {
+    Counter.instances = 0;
+  }
+
+  constructor(start) {
+    this.count = start;
+  }
+
+  get step() {
+    return this.#step;
+  }
+
+  increment() {
+    this.count += this.#step;
+  }
+}
+
+class Timer extends Counter {
+  handle = null;
+  exports = [];
+  module;
+  require = () => {};
+}
+
+const Anonymous = class {
+  field;
+};
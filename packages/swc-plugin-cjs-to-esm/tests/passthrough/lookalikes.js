@@ -0,0 +1,12 @@
+const plugin = {
+  exports: {},
+  require(name) {
+    return name;
+  },
+};
+plugin.exports.value = 1;
+plugin.require('foo');
+
+const { exports: moduleExports, module: inner } = plugin;
+const modules = { exports: moduleExports };
+console.log(typeof module, typeof exports, modules.exports, inner);
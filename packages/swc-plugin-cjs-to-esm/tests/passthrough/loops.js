@@ -0,0 +1,40 @@
+for (let i = 0; i < 10; i++) {
+  console.log(i);
+}
+
+for (let i, j; ; ) {
+  break;
+}
+
+for (const item of items) {
+  handle(item);
+}
+
+for (const [key, value] of Object.entries(map)) {
+  handle(key, value);
+}
+
+for (var key in object) {
+  handle(key);
+}
+
+for (let { a, b: [c] } of pairs) {
+  handle(a, c);
+}
+
+let index;
+for (index of indexes) {
+  handle(index);
+}
+
+async function read(stream) {
+  for await (const chunk of stream) {
+    handle(chunk);
+  }
+}
+
+outer: while (true) {
+  do {
+    continue outer;
+  } while (false);
+}
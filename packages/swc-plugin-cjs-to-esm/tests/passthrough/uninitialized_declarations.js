@@ -0,0 +1,20 @@
+let foo;
+var bar, baz;
+let qux, quux = 1;
+const [first] = list;
+
+function later() {
+  let result;
+  var cached;
+  if (cached) {
+    result = cached;
+  }
+  return result;
+}
+
+try {
+  run();
+} catch {
+  let error;
+  report(error);
+}
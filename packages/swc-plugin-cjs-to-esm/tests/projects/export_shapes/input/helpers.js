@@ -0,0 +1 @@
+exports.greet = (name) => `hello ${name}`;
@@ -0,0 +1,9 @@
+const helpers = require('./helpers');
+const { add, subtract } = require('./math');
+const { pi, tau } = require('./math');
+const logger = require('./lib');
+const { level } = require('./lib');
+const name = require('./config.js').name;
+const lodash = require('lodash');
+
+module.exports = { run: () => logger(helpers.greet(name), add(pi, tau), subtract(1, level)) };
@@ -0,0 +1,3 @@
+const add = (a, b) => a + b;
+const subtract = (a, b) => a - b;
+module.exports = { add, subtract, pi: Math.PI };
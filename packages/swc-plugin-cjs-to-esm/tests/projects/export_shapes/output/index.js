@@ -0,0 +1,11 @@
+import * as helpers from './helpers';
+import { add, subtract } from './math';
+import * as _math$1 from './math';
+import logger from './lib';
+import _mod$1 from './config.js';
+import * as lodash from 'lodash';
+const { pi , tau  } = _math$1;
+const { level  } = logger;
+const name = _mod$1.name;
+const _run$1 = ()=>logger(helpers.greet(name), add(pi, tau), subtract(1, level));
+export { _run$1 as run };
@@ -0,0 +1,3 @@
+export default function log(...args) {
+    console.log(...args);
+}
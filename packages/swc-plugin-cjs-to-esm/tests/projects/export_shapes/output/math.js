@@ -0,0 +1,4 @@
+const add = (a, b)=>a + b;
+const subtract = (a, b)=>a - b;
+const _pi$1 = Math.PI;
+export { add, subtract, _pi$1 as pi };
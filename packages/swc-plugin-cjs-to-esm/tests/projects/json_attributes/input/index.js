@@ -0,0 +1,5 @@
+const path = require('path');
+const config = require('./config.json');
+const { port } = require('./defaults');
+
+module.exports = { config, port, root: path.resolve(__dirname, config.root) };
@@ -0,0 +1,13 @@
+import { fileURLToPath } from 'url';
+import { dirname } from 'path';
+import * as path from 'path';
+import config from './config.json' with {
+    type: 'json'
+};
+import _defaults$1 from './defaults.json' with {
+    type: 'json'
+};
+const { port  } = _defaults$1;
+const __dirname = dirname(fileURLToPath(import.meta.url));
+const _root$1 = path.resolve(__dirname, config.root);
+export { config, port, _root$1 as root };
@@ -0,0 +1,10 @@
+const fs = require('fs');
+const cjs = require('cjs-pkg');
+const { map, filter } = require('cjs-pkg');
+const esm = require('esm-type');
+const { named } = require('esm-exports');
+const sub = require('@scope/cjs/sub');
+const missing = require('not-installed');
+const version = require('cjs-pkg').version;
+
+module.exports = { fs, cjs, map, filter, esm, named, sub, missing, version };
@@ -0,0 +1,9 @@
+import * as fs from 'fs';
+import cjs from 'cjs-pkg';
+import * as esm from 'esm-type';
+import { named } from 'esm-exports';
+import sub from '@scope/cjs/sub';
+import * as missing from 'not-installed';
+const { map , filter  } = cjs;
+const version = cjs.version;
+export { fs, cjs, map, filter, esm, named, sub, missing, version };
@@ -0,0 +1 @@
+exports.helper = () => import('./util');
@@ -0,0 +1,10 @@
+const fs = require('fs');
+const util = require('./util');
+const { log } = require("./lib");
+const settings = require('./data/settings.json');
+const { retries } = require('./data/defaults');
+const { helper } = require('./helper.js');
+const legacy = require('./legacy');
+const missing = require('./missing');
+
+module.exports = { read: () => fs.readFileSync(util.path(settings)), log, helper, legacy, missing, retries };
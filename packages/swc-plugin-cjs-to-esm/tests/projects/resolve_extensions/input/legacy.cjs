@@ -0,0 +1 @@
+exports.legacy = true;
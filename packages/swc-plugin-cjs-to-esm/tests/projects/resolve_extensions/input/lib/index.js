@@ -0,0 +1 @@
+exports.log = console.log;
@@ -0,0 +1 @@
+exports.path = (settings) => settings.path;
@@ -0,0 +1,11 @@
+import * as fs from 'fs';
+import * as util from './util.mjs';
+import { log } from "./lib/index.mjs";
+import settings from './data/settings.json';
+import _defaults$1 from './data/defaults.json';
+import { helper } from './helper.mjs';
+import * as legacy from './legacy.cjs';
+import * as missing from './missing';
+const { retries  } = _defaults$1;
+const _read$1 = ()=>fs.readFileSync(util.path(settings));
+export { _read$1 as read, log, helper, legacy, missing, retries };
@@ -0,0 +1 @@
+export const path = (settings)=>settings.path;
@@ -0,0 +1,12 @@
+import { join } from 'path';
+const { readFileSync } = require('fs');
+
+export const name = 'config';
+export function load(file) {
+  return readFileSync(join(__dirname, file), 'utf8');
+}
+export default load;
+
+exports.name = 'settings';
+exports.load = load;
+module.exports = () => load('default.json');
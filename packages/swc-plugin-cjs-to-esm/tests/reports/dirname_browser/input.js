@@ -0,0 +1,3 @@
+const assets = require('./assets');
+
+module.exports = assets.url(__dirname, __filename);
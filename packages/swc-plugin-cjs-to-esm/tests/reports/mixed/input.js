@@ -0,0 +1,16 @@
+require('./polyfills');
+const fs = require('fs');
+const { join, resolve: resolvePath } = require('path');
+const { a, ...rest } = require('./rest');
+const value = require('./config').value;
+
+function load(name) {
+  return require(name);
+}
+
+module.exports.load = load;
+exports.VERSION = '1.0.0';
+if (module.hot) {
+  module.hot.accept();
+}
+module.exports = function main() {};
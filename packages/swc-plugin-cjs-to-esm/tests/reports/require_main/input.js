@@ -0,0 +1,15 @@
+const run = require('./run');
+
+if (require.main === module) {
+  run(require.main.filename);
+}
+
+if (module.parent) {
+  console.log('required by', module.parent.filename);
+}
+
+delete require.cache[require.resolve('./run')];
+
+module.exports = run;
+
+const findMain = (require) => require.main;
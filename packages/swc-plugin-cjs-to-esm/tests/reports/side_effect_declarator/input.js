@@ -0,0 +1,2 @@
+const fs = require('fs'), start = Date.now(), config = require('./config');
+module.exports = { fs, start, config };
@@ -0,0 +1,5 @@
+const fs = require('fs');
+console.log('loading');
+const config = require('./config');
+require('./polyfills');
+exports.config = config;
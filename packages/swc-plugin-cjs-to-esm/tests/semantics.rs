@@ -0,0 +1,252 @@
+//! Runs the module graphs in `tests/semantics` once as CommonJS and once converted to ES modules,
+//! and checks that both log the same things in the same order and that the entry exports the same values.
+//!
+//! Each graph is a directory with an `index.js` entry. Modules can call `log(...)` to record side effects.
+//! A graph with a `known_divergence` file is expected to behave differently, the file says why.
+//! The plugin config for a graph can be set with a `config.json` next to `index.js`.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use boa_engine::{
+    builtins::promise::PromiseState,
+    module::{ModuleLoader, Referrer},
+    Context, JsArgs, JsError, JsNativeError, JsObject, JsResult, JsString, JsValue, Module, NativeFunction, Source,
+};
+use swc_plugin_cjs_to_esm::{
+    config::CjsToEsmConfig, project::ExportTable, resolve::resolve_relative, transform_source, Options,
+};
+
+// `log()` and a way to describe exports that can be compared as text
+const PRELUDE: &str = r#"
+globalThis.__log = [];
+globalThis.log = (...args) => { __log.push(args.map(String).join(' ')); };
+globalThis.__describe = function describe(value) {
+    // Names are left out, `export const foo = () => {}` names the function while `exports.foo = () => {}` doesn't
+    if (typeof value === 'function') return '[function]';
+    if (Array.isArray(value)) return '[' + value.map(describe).join(', ') + ']';
+    if (value !== null && typeof value === 'object') {
+        return '{ ' + Object.keys(value).sort().map((key) => key + ': ' + describe(value[key])).join(', ') + ' }';
+    }
+    return typeof value === 'string' ? JSON.stringify(value) : String(value);
+};
+"#;
+
+// A minimal `require` following the same resolution as `resolve_relative()`
+const CJS_LOADER: &str = r#"
+function __require(from, specifier) {
+    const parts = from.split('/').slice(0, -1);
+    for (const part of specifier.split('/')) {
+        if (part === '..') parts.pop();
+        else if (part !== '.') parts.push(part);
+    }
+    const base = parts.join('/');
+    const candidates = [base, base + '.js', base + '.cjs', base + '.json', base + '.jsx', base + '/index.js'];
+    const path = candidates.find((candidate) => candidate in __modules);
+    if (path === undefined) throw new Error(`cannot find '${specifier}' from '${from}'`);
+    if (!(path in __cache)) {
+        const module = { exports: {} };
+        __cache[path] = module;
+        __modules[path](module.exports, (specifier) => __require(path, specifier), module);
+    }
+    return __cache[path].exports;
+}
+const __cache = {};
+"#;
+
+// Node's `module`, where the `require` of `createRequire()` loads and evaluates the converted file right away,
+// like node 22.12 and newer do when requiring ES modules. Older versions throw `ERR_REQUIRE_ESM` instead
+const NODE_MODULE: &str = r#"
+export function createRequire(url) {
+    return (specifier) => __requireModule(url, specifier);
+}
+"#;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Observed {
+    log: Vec<String>,
+    exports: String,
+}
+
+#[testing::fixture("tests/semantics/*/index.js")]
+fn semantics(entry: PathBuf) {
+    let dir = entry.parent().unwrap();
+    let files: HashMap<PathBuf, String> = glob::glob(&format!("{}/**/*.js", dir.display()))
+        .unwrap()
+        .flatten()
+        .map(|file| {
+            let src = std::fs::read_to_string(&file).unwrap();
+            (file.strip_prefix(dir).unwrap().to_owned(), src)
+        })
+        .collect();
+
+    let cjs = run_cjs(&files);
+    // Converted like `cjs-to-esm --project` would, so requires of files with a default export import it
+    let paths: Vec<PathBuf> = files.keys().map(|path| dir.join(path)).collect();
+    let config: CjsToEsmConfig = std::fs::read_to_string(dir.join("config.json"))
+        .map(|config| serde_json::from_str(&config).unwrap())
+        .unwrap_or_default();
+    let options = Options { project: Some(Rc::new(ExportTable::scan(&paths, &config))), config, ..Default::default() };
+    let converted = files
+        .iter()
+        .map(|(path, src)| {
+            let output = transform_source(src, &dir.join(path).to_string_lossy(), &options).unwrap();
+            (path.clone(), output.code)
+        })
+        .collect();
+    let esm = run_esm(converted);
+
+    if dir.join("known_divergence").exists() {
+        assert_ne!(cjs, esm, "{} behaves the same now, remove known_divergence", dir.display());
+    } else {
+        assert_eq!(cjs, esm, "converting {} changed what it does", dir.display());
+    }
+}
+
+fn run_cjs(files: &HashMap<PathBuf, String>) -> Observed {
+    let mut modules = String::from("const __modules = {\n");
+    for (path, src) in files {
+        modules.push_str(&format!(
+            "{:?}: function (exports, require, module) {{\n{}\n}},\n",
+            path.to_string_lossy(),
+            src
+        ));
+    }
+    modules.push_str("};\n");
+
+    let mut context = Context::default();
+    eval(&mut context, PRELUDE);
+    eval(&mut context, &format!("{}{}", modules, CJS_LOADER));
+    let exports = eval(&mut context, "__describe(__require('', './index.js'))");
+    observe(&mut context, exports)
+}
+
+fn run_esm(mut files: HashMap<PathBuf, String>) -> Observed {
+    files.insert(PathBuf::from("module"), NODE_MODULE.to_owned());
+    let loader = Rc::new(GraphLoader { files, modules: Default::default(), paths: Default::default() });
+    let mut context = Context::builder().module_loader(loader.clone()).build().unwrap();
+    eval(&mut context, PRELUDE);
+    context
+        .register_global_callable(JsString::from("__requireModule"), 2, NativeFunction::from_fn_ptr(require_module))
+        .unwrap();
+
+    let entry = loader.load(Path::new("index.js"), &mut context).unwrap();
+    let promise = entry.load_link_evaluate(&mut context);
+    context.run_jobs();
+    if let PromiseState::Rejected(err) = promise.state() {
+        panic!("{}", JsError::from_opaque(err).to_string());
+    }
+
+    // A module that only has a default export stands for what `module.exports` was set to
+    let namespace = entry.namespace(&mut context);
+    context.global_object().set(JsString::from("__namespace"), namespace, false, &mut context).unwrap();
+    let exports = eval(
+        &mut context,
+        "__describe(Object.keys(__namespace).join() === 'default' ? __namespace.default : { ...__namespace })",
+    );
+    observe(&mut context, exports)
+}
+
+// `__requireModule(import.meta.url, specifier)`, see `NODE_MODULE`
+fn require_module(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let url = args.get_or_undefined(0).to_string(context)?.to_std_string_escaped();
+    let specifier = args.get_or_undefined(1).to_string(context)?.to_std_string_escaped();
+    // The loader resolves specifiers of anything but modules from the root of the graph
+    let importer = Path::new(url.trim_start_matches("file:///"));
+    let from_root = importer.parent().unwrap_or(Path::new("")).join(&specifier);
+    let loaded = Rc::new(RefCell::new(None));
+    let slot = loaded.clone();
+    context.module_loader().load_imported_module(
+        Referrer::Realm(context.realm().clone()),
+        JsString::from(format!("./{}", from_root.display())),
+        Box::new(move |module, _| *slot.borrow_mut() = Some(module)),
+        context,
+    );
+    let module = loaded.take().expect("the graph loader loads right away")?;
+    let promise = module.load_link_evaluate(context);
+    context.run_jobs();
+    if let PromiseState::Rejected(err) = promise.state() {
+        return Err(JsError::from_opaque(err));
+    }
+    Ok(module.namespace(context).into())
+}
+
+fn observe(context: &mut Context, exports: JsValue) -> Observed {
+    let log = eval(context, "__log.join('\\n')");
+    let log = log.to_string(context).unwrap().to_std_string_escaped();
+    Observed {
+        log: log.lines().map(str::to_owned).collect(),
+        exports: exports.to_string(context).unwrap().to_std_string_escaped(),
+    }
+}
+
+fn eval(context: &mut Context, src: &str) -> JsValue {
+    context
+        .eval(Source::from_bytes(src))
+        .unwrap_or_else(|err| panic!("{}\n{}", err, src))
+}
+
+/**
+    Serves the converted files of a graph, resolving specifiers relative to the importing file.
+ */
+struct GraphLoader {
+    files: HashMap<PathBuf, String>,
+    modules: RefCell<HashMap<PathBuf, Module>>,
+    paths: RefCell<HashMap<Module, PathBuf>>,
+}
+
+impl GraphLoader {
+    fn load(&self, path: &Path, context: &mut Context) -> JsResult<Module> {
+        if let Some(module) = self.modules.borrow().get(path) {
+            return Ok(module.clone());
+        }
+        let src = self
+            .files
+            .get(path)
+            .ok_or_else(|| JsNativeError::typ().with_message(format!("no file '{}'", path.display())))?;
+        let module = Module::parse(Source::from_bytes(src), None, context)?;
+        self.modules.borrow_mut().insert(path.to_owned(), module.clone());
+        self.paths.borrow_mut().insert(module.clone(), path.to_owned());
+        Ok(module)
+    }
+}
+
+impl ModuleLoader for GraphLoader {
+    fn load_imported_module(
+        &self,
+        referrer: Referrer,
+        specifier: JsString,
+        finish_load: Box<dyn FnOnce(JsResult<Module>, &mut Context)>,
+        context: &mut Context,
+    ) {
+        let importer = match referrer {
+            Referrer::Module(module) => self.paths.borrow().get(&module).cloned().unwrap_or_default(),
+            _ => PathBuf::from("index.js"),
+        };
+        let specifier = specifier.to_std_string_escaped();
+        let resolved = if specifier == "module" {
+            Some(PathBuf::from("module"))
+        } else {
+            resolve_relative(&importer, &specifier, |path| self.files.contains_key(path))
+        };
+        let result = resolved
+            .ok_or_else(|| {
+                JsNativeError::typ()
+                    .with_message(format!("cannot find '{}' from '{}'", specifier, importer.display()))
+                    .into()
+            })
+            .and_then(|path| self.load(&path, context));
+        finish_load(result, context);
+    }
+
+    fn init_import_meta(&self, import_meta: &JsObject, module: &Module, context: &mut Context) {
+        if let Some(path) = self.paths.borrow().get(module) {
+            let url = JsString::from(format!("file:///{}", path.display()));
+            import_meta.set(JsString::from("url"), url, false, context).unwrap();
+        }
+    }
+}
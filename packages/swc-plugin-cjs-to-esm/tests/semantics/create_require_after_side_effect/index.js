@@ -0,0 +1,4 @@
+log('start');
+const name = require('./lib/config').name;
+log('name', name);
+exports.name = name;
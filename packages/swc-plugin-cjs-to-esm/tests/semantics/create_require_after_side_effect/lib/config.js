@@ -0,0 +1,3 @@
+log('config');
+const { label } = require('./label');
+exports.name = label + ' config';
@@ -0,0 +1,2 @@
+log('label');
+exports.label = 'lib';
@@ -0,0 +1,6 @@
+const greet = require('./greet');
+const Counter = require('./counter');
+const counter = new Counter();
+counter.increment();
+log(greet('world'), counter.count);
+module.exports = greet;
@@ -0,0 +1,2 @@
+log('config');
+exports.name = 'config';
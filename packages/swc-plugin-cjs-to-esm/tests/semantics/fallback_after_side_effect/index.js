@@ -0,0 +1,4 @@
+log('start');
+const name = require('./config').name;
+log('name', name);
+exports.name = name;
@@ -0,0 +1,2 @@
+log('constants');
+exports.prefix = 'v';
@@ -0,0 +1,5 @@
+const lib = require('./lib');
+const { format } = require('./lib/format');
+log(format(lib.version));
+exports.version = lib.version;
+exports.format = format;
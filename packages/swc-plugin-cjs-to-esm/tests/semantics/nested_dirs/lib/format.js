@@ -0,0 +1,2 @@
+log('format');
+exports.format = (version) => `version ${version}`;
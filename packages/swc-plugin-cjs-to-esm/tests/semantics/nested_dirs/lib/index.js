@@ -0,0 +1,3 @@
+const { prefix } = require('../constants');
+log('lib');
+module.exports = { version: prefix + '1.0.0' };
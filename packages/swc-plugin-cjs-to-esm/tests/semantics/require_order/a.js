@@ -0,0 +1,2 @@
+log('a');
+exports.name = 'a';
@@ -0,0 +1,3 @@
+log('b');
+exports.b = 2;
+exports.double = (n) => n * 2;
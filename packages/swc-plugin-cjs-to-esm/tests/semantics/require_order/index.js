@@ -0,0 +1,5 @@
+require('./setup');
+const a = require('./a');
+const { b, double } = require('./b');
+log('index', a.name, double(b));
+module.exports = { a: a.name, b };
@@ -0,0 +1 @@
+log('setup');
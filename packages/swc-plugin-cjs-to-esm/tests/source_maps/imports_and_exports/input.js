@@ -0,0 +1,11 @@
+const fs = require('fs');
+const { join } = require('path');
+require('./setup');
+const value = require('./config').value;
+
+function read(file) {
+  return fs.readFileSync(join(__dirname, file));
+}
+
+exports.read = read;
+module.exports.value = value * 2;
@@ -0,0 +1,5 @@
+/* cjs-to-esm-disable */
+const { load } = require('./loader');
+
+
+module.exports = load(require.resolve("./config.json"))